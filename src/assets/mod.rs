@@ -32,8 +32,7 @@ impl Plugin for AssetsPlugin {
             .add_plugin(MaterialPlugin::<CustomMaterial>::default())
             .add_plugin(MaterialPlugin::<EmissiveMaterial>::default())
             .add_plugin(MaterialPlugin::<LightShaftMaterial>::default())
-            .add_plugin(MaterialPlugin::<OrbMaterial>::default())
-            .add_state(GameState::Menu);
+            .add_plugin(MaterialPlugin::<OrbMaterial>::default());
     }
 }
 
@@ -42,6 +41,10 @@ pub enum GameState {
     Loading,
     Menu,
     Playing,
+    /// Pushed on top of `Playing` when the player dies
+    GameOver,
+    /// Pushed on top of `Playing` while the pause menu is open
+    Paused,
 }
 
 #[derive(AssetCollection)]
@@ -251,7 +254,7 @@ impl AudioAssets {
         .choose(&mut rand::thread_rng())
         .unwrap()
     }
-    pub fn get_step(&self) -> &Handle<AudioSource> {
+    pub fn steps(&self) -> [&Handle<AudioSource>; 10] {
         [
             &self.step01,
             &self.step02,
@@ -264,8 +267,6 @@ impl AudioAssets {
             &self.step09,
             &self.step10,
         ]
-        .choose(&mut rand::thread_rng())
-        .unwrap()
     }
     pub fn get_lasergun(&self) -> &Handle<AudioSource> {
         [
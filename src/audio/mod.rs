@@ -1,24 +1,36 @@
 use bevy::{prelude::*, utils::HashMap};
-use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin};
+use bevy_kira_audio::{Audio, AudioChannel, AudioPlugin, AudioSource, OggLoader};
+use rand::prelude::SliceRandom;
 
 use crate::{
     assets::{AudioAssets, GameState},
-    player::PlayerEvent,
+    enemies::EnemyEvent,
+    player::{HitKind, PlayerEvent},
+    world::LevelMesh,
+    GameClock,
 };
 
+/// Movement sounds take turns on this many channels, so setting the volume and rate of one
+/// doesn't change the ones still playing
+const MOVEMENT_CHANNELS: usize = 4;
+
 impl Plugin for GameAudioPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(AudioPlugin)
             .insert_resource(AudioState::default())
             .insert_resource(EnvironmentAudio::default())
+            .init_resource::<MovementSounds>()
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing).with_system(setup_audio_channels),
             )
             .add_system_set(
                 SystemSet::on_update(GameState::Playing)
                     .with_system(fade_in_atmosphere)
-                    .with_system(player_audio_events),
-            );
+                    .with_system(player_audio_events)
+                    .with_system(enemy_audio_events),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(pause_audio))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(resume_audio));
     }
 }
 
@@ -26,6 +38,9 @@ fn setup_audio_channels(
     mut audio_state: ResMut<AudioState>,
     mut env_audio: ResMut<EnvironmentAudio>,
 ) {
+    env_audio.movement = (0..MOVEMENT_CHANNELS)
+        .map(|i| AudioChannel::new(format!("movement{}", i)))
+        .collect();
     let atmosphere = AudioChannel::new("atmosphere".to_owned());
     env_audio.atmosphere = Some(atmosphere.clone());
     audio_state.channels.insert(
@@ -43,8 +58,23 @@ fn setup_audio_channels(
     );
 }
 
+/// Holds every sound where it is while the game is paused
+fn pause_audio(audio: Res<Audio>, env_audio: Res<EnvironmentAudio>) {
+    audio.pause();
+    for channel in env_audio.channels() {
+        audio.pause_channel(channel);
+    }
+}
+
+fn resume_audio(audio: Res<Audio>, env_audio: Res<EnvironmentAudio>) {
+    audio.resume();
+    for channel in env_audio.channels() {
+        audio.resume_channel(channel);
+    }
+}
+
 fn fade_in_atmosphere(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     audio: Res<Audio>,
     mut audio_state: ResMut<AudioState>,
     audio_assets: Res<AudioAssets>,
@@ -54,13 +84,13 @@ fn fade_in_atmosphere(
         if let Some(atmosphere_state) = audio_state.channels.get_mut(atmosphere_ch) {
             if atmosphere_state.stopped {
                 audio.play_looped_in_channel(audio_assets.atmosphere.clone(), atmosphere_ch);
-                atmosphere_state.time_started = time.time_since_startup().as_secs_f32();
+                atmosphere_state.time_started = clock.seconds();
                 atmosphere_state.stopped = false;
             }
             let mut level = from_range(
                 0.0,
                 atmosphere_state.fade_in_time,
-                time.time_since_startup().as_secs_f32() - atmosphere_state.time_started,
+                clock.seconds() - atmosphere_state.time_started,
             )
             .clamp(0.0, 1.0);
             level *= db_to_lin(atmosphere_state.final_volume);
@@ -70,25 +100,167 @@ fn fade_in_atmosphere(
     }
 }
 
+/// A group of step samples and how to play them
+pub struct StepSoundSet {
+    /// Indices into [`AudioAssets::steps`]
+    pub samples: Vec<usize>,
+    /// Volume in decibels
+    pub volume: f32,
+    pub playback_rate: f32,
+}
+
+impl StepSoundSet {
+    fn new(samples: &[usize], volume: f32, playback_rate: f32) -> Self {
+        StepSoundSet {
+            samples: samples.to_vec(),
+            volume,
+            playback_rate,
+        }
+    }
+}
+
+/// Which step sounds play on each part of the level, and for jumping and landing
+pub struct MovementSounds {
+    /// Used when no level mesh was found under the player
+    pub default: StepSoundSet,
+    pub surfaces: HashMap<LevelMesh, StepSoundSet>,
+    /// Added to the volume of sprinting steps, in decibels
+    pub sprint_volume: f32,
+    pub jump: StepSoundSet,
+    pub land: StepSoundSet,
+    /// Extra landing volume per second spent in the air, in decibels
+    pub land_volume_per_second: f32,
+}
+
+impl Default for MovementSounds {
+    fn default() -> Self {
+        let all = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        MovementSounds {
+            default: StepSoundSet::new(&all, 0.0, 1.0),
+            surfaces: [
+                (LevelMesh::Walls, StepSoundSet::new(&all, 0.0, 0.95)),
+                (LevelMesh::SpheresBase, StepSoundSet::new(&all, -1.0, 0.9)),
+                (
+                    LevelMesh::Pillars,
+                    StepSoundSet::new(&[2, 4, 6, 8], -2.0, 1.05),
+                ),
+                // Metal rings higher
+                (
+                    LevelMesh::Spheres,
+                    StepSoundSet::new(&[0, 1, 3, 5], 0.0, 1.25),
+                ),
+                (
+                    LevelMesh::LargeCeilingSupports,
+                    StepSoundSet::new(&[0, 1, 3, 5], 0.0, 1.15),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            sprint_volume: 2.0,
+            jump: StepSoundSet::new(&[1, 3, 7], -6.0, 1.3),
+            land: StepSoundSet::new(&all, 0.0, 0.75),
+            land_volume_per_second: 6.0,
+        }
+    }
+}
+
+fn play_step_sound(
+    audio: &Audio,
+    audio_assets: &AudioAssets,
+    channel: &AudioChannel,
+    set: &StepSoundSet,
+    extra_volume: f32,
+) {
+    let steps = audio_assets.steps();
+    if let Some(sample) = set
+        .samples
+        .choose(&mut rand::thread_rng())
+        .and_then(|i| steps.get(*i))
+    {
+        audio.set_volume_in_channel(db_to_lin(set.volume + extra_volume), channel);
+        audio.set_playback_rate_in_channel(set.playback_rate, channel);
+        audio.play_in_channel((*sample).clone(), channel);
+    }
+}
+
 fn player_audio_events(
     mut player_events: EventReader<PlayerEvent>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
+    mut env_audio: ResMut<EnvironmentAudio>,
+    movement_sounds: Res<MovementSounds>,
 ) {
     for player_event in player_events.iter() {
         match player_event {
-            PlayerEvent::Hit { laser } => {
-                if !laser {
+            PlayerEvent::Hit { kind, .. } => {
+                if *kind != HitKind::Laser {
                     audio.play(audio_assets.get_hurt().clone());
                 }
             }
-            PlayerEvent::Fire { alt } => {
+            PlayerEvent::ShieldBroken => {
+                audio.play(audio_assets.get_hurt().clone());
+            }
+            PlayerEvent::Fire { alt, .. } => {
                 if *alt {
                     audio.play(audio_assets.get_lasergun_alt().clone());
                 } else {
                     audio.play(audio_assets.get_lasergun().clone());
                 }
             }
+            PlayerEvent::Footstep { surface, sprinting } => {
+                if let Some(channel) = env_audio.next_movement_channel() {
+                    let set = surface
+                        .and_then(|surface| movement_sounds.surfaces.get(&surface))
+                        .unwrap_or(&movement_sounds.default);
+                    let extra_volume = if *sprinting {
+                        movement_sounds.sprint_volume
+                    } else {
+                        0.0
+                    };
+                    play_step_sound(&audio, &audio_assets, &channel, set, extra_volume);
+                }
+            }
+            PlayerEvent::Ultimate { .. } => {
+                audio.play(audio_assets.get_unit2_explosion().clone());
+            }
+            PlayerEvent::Jump => {
+                if let Some(channel) = env_audio.next_movement_channel() {
+                    play_step_sound(&audio, &audio_assets, &channel, &movement_sounds.jump, 0.0);
+                }
+            }
+            PlayerEvent::Land { air_time } => {
+                if let Some(channel) = env_audio.next_movement_channel() {
+                    let extra_volume = (air_time * movement_sounds.land_volume_per_second).min(6.0);
+                    play_step_sound(
+                        &audio,
+                        &audio_assets,
+                        &channel,
+                        &movement_sounds.land,
+                        extra_volume,
+                    );
+                }
+            }
+            PlayerEvent::LaserImpact { .. } => {}
+        }
+    }
+}
+
+fn enemy_audio_events(
+    mut enemy_events: EventReader<EnemyEvent>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for enemy_event in enemy_events.iter() {
+        match enemy_event {
+            EnemyEvent::Fired { .. } => {
+                audio.play(audio_assets.get_unit2_fire().clone());
+            }
+            EnemyEvent::BulletHit { .. } => {
+                audio.play(audio_assets.get_unit2_projectile_collide().clone());
+            }
+            EnemyEvent::Killed { .. } => {
+                audio.play(audio_assets.get_unit2_explosion().clone());
+            }
         }
     }
 }
@@ -148,6 +320,34 @@ struct AudioState {
 #[derive(Default)]
 struct EnvironmentAudio {
     pub atmosphere: Option<AudioChannel>,
+    /// Footsteps, jumps and landings, rate and volume are set per sound
+    pub movement: Vec<AudioChannel>,
+    /// Index of the movement channel the next sound plays on
+    next_movement: usize,
+}
+
+impl EnvironmentAudio {
+    fn channels(&self) -> impl Iterator<Item = &AudioChannel> {
+        self.atmosphere.iter().chain(self.movement.iter())
+    }
+
+    /// The movement channel that played longest ago, or none before the channels are set up
+    fn next_movement_channel(&mut self) -> Option<AudioChannel> {
+        let channel = self.movement.get(self.next_movement)?.clone();
+        self.next_movement = (self.next_movement + 1) % self.movement.len();
+        Some(channel)
+    }
 }
 
 pub struct GameAudioPlugin;
+
+/// Stands in for [`GameAudioPlugin`] with the sound off. The sound files still load, but no audio
+/// device is opened.
+pub struct SilentAudioPlugin;
+
+impl Plugin for SilentAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AudioSource>()
+            .init_asset_loader::<OggLoader>();
+    }
+}
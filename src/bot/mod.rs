@@ -0,0 +1,266 @@
+use std::{cmp::Ordering, f32::consts::PI};
+
+use bevy::{app::AppExit, math::Vec3Swizzles, prelude::*};
+use bevy_asset_loader::DynamicAssets;
+use rand::{prelude::SliceRandom, Rng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    assets::GameState,
+    enemies::{Alive, Enemy, EnemySpawnTimer, Waypoints},
+    input::{Action, ActionInputSystem, ActionState},
+    player::{InputState, Player},
+    run::{replay::BeginRunSystem, start_run, GameRng, ResetRun, RunAppExt},
+    ui::scoreboard::Scoreboard,
+    GameClock, GameplaySystem,
+};
+
+/// Closer than this counts as having reached a waypoint
+const WAYPOINT_RADIUS: f32 = 2.0;
+/// Distance the bot must cover every second before it counts as stuck
+const STUCK_DISTANCE: f32 = 0.5;
+/// Only use the secondary fire on enemies closer than this
+const ALT_FIRE_RANGE: f32 = 40.0;
+
+/// Plays the game in place of the mouse and keyboard, for balance testing
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotSettings>()
+            .init_run_resource::<BotState>()
+            .init_resource::<BotReport>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                bot_input.after(ActionInputSystem).after(BeginRunSystem),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(bot_start_game))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver)
+                    .with_system(bot_finish_run.before(GameplaySystem::Reset)),
+            );
+    }
+}
+
+pub struct BotSettings {
+    pub enabled: bool,
+    /// Seconds between seeing a new target and reacting to it
+    pub reaction_time: f32,
+    /// Largest aim offset in radians, rerolled for every new target
+    pub aim_error: f32,
+    /// Fastest the bot can turn, in radians per second
+    pub turn_speed: f32,
+    /// Fire once the aim is this close to the target, in radians
+    pub fire_cone: f32,
+    /// Exit the game after this many runs, 0 keeps playing forever
+    pub runs: usize,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        BotSettings {
+            enabled: false,
+            reaction_time: 0.25,
+            aim_error: 0.03,
+            turn_speed: 8.0,
+            fire_cone: 0.05,
+            runs: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct BotState {
+    waypoint: Option<Vec3>,
+    target: Option<Entity>,
+    /// Time left before the bot reacts to its current target
+    reaction: f32,
+    aim_offset: Vec2,
+    stuck_timer: f32,
+    last_position: Vec3,
+    /// Taken from the run's seed, apart from the gameplay rolls so a replay of the run, which
+    /// doesn't have the bot, rolls them the same
+    rng: Option<ChaCha8Rng>,
+}
+
+pub struct RunResult {
+    pub time_survived: f32,
+    pub level: usize,
+    pub score: usize,
+}
+
+/// Results of every run the bot has finished
+#[derive(Default)]
+pub struct BotReport {
+    pub runs: Vec<RunResult>,
+}
+
+impl BotReport {
+    pub fn print_summary(&self) {
+        let count = self.runs.len().max(1) as f32;
+        let mean_time = self.runs.iter().map(|run| run.time_survived).sum::<f32>() / count;
+        let mean_level = self.runs.iter().map(|run| run.level as f32).sum::<f32>() / count;
+        let max_level = self.runs.iter().map(|run| run.level).max().unwrap_or(0);
+        info!(
+            "Bot finished {} runs: mean time to death {:.1}s, mean level {:.2}, max level {}",
+            self.runs.len(),
+            mean_time,
+            mean_level,
+            max_level
+        );
+    }
+}
+
+/// Yaw and pitch that look from `from` toward `to`, matching the player's look angles
+fn look_angles(from: Vec3, to: Vec3) -> Vec2 {
+    let dir = (to - from).normalize_or_zero();
+    Vec2::new((-dir.x).atan2(-dir.z), dir.y.clamp(-1.0, 1.0).asin())
+}
+
+/// Wraps an angle into -PI..PI
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
+
+/// Replaces whatever the devices put into [`ActionState`] with the bot's own input
+fn bot_input(
+    clock: Res<GameClock>,
+    game_rng: Res<GameRng>,
+    settings: Res<BotSettings>,
+    mut bot: ResMut<BotState>,
+    mut action_state: ResMut<ActionState>,
+    look: Res<InputState>,
+    waypoints: Res<Waypoints>,
+    enemy_spawn_timer: Res<EnemySpawnTimer>,
+    players: Query<(&Transform, &Player)>,
+    enemies: Query<(Entity, &Transform), (With<Enemy>, With<Alive>, Without<Player>)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    // The frame has already begun, the menu stays with whoever watches
+    action_state.release_all_except(&[Action::Menu]);
+    let (player_transform, player) = match players.iter().next() {
+        Some(player) => player,
+        None => return,
+    };
+    if player.health <= 0.0 {
+        return;
+    }
+    let dt = clock.delta_seconds();
+    let bot = &mut *bot;
+    let rng = bot.rng.get_or_insert_with(|| game_rng.side_stream());
+    let position = player_transform.translation;
+
+    // Wander between inside waypoints, picking a new one when reached or stuck
+    bot.stuck_timer += dt;
+    if bot.stuck_timer > 1.0 {
+        if position.distance(bot.last_position) < STUCK_DISTANCE {
+            bot.waypoint = None;
+        }
+        bot.stuck_timer = 0.0;
+        bot.last_position = position;
+    }
+    if bot.waypoint.map_or(true, |waypoint| {
+        (waypoint - position).xz().length() < WAYPOINT_RADIUS
+    }) {
+        bot.waypoint = waypoints.inside.choose(rng).copied();
+    }
+    if let Some(waypoint) = bot.waypoint {
+        let to_waypoint = (waypoint - position).xz().normalize_or_zero();
+        let (sin, cos) = look.yaw.sin_cos();
+        let right = Vec2::new(cos, -sin);
+        let forward = Vec2::new(-sin, -cos);
+        action_state.move_axis = Vec2::new(to_waypoint.dot(right), to_waypoint.dot(forward));
+    }
+
+    // Aim at the nearest enemy, after a moment to react
+    let eye = position + Vec3::Y * 1.82;
+    let nearest = enemies
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation))
+        .min_by(|a, b| {
+            a.1.distance_squared(eye)
+                .partial_cmp(&b.1.distance_squared(eye))
+                .unwrap_or(Ordering::Equal)
+        });
+    let (target, target_position) = match nearest {
+        Some(nearest) => nearest,
+        None => {
+            bot.target = None;
+            // Firing starts the enemy spawner at the beginning of a run
+            if enemy_spawn_timer.0.paused() {
+                action_state.press(Action::AltFire);
+            }
+            return;
+        }
+    };
+    if bot.target != Some(target) {
+        bot.target = Some(target);
+        bot.reaction = settings.reaction_time;
+        bot.aim_offset =
+            Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * settings.aim_error;
+    }
+    if bot.reaction > 0.0 {
+        bot.reaction -= dt;
+        return;
+    }
+    let desired = look_angles(eye, target_position) + bot.aim_offset;
+    let error = Vec2::new(wrap_angle(desired.x - look.yaw), desired.y - look.pitch);
+    action_state.look_delta = error.clamp_length_max(settings.turn_speed * dt);
+
+    if error.length() < settings.fire_cone {
+        action_state.press(Action::Fire);
+        if eye.distance(target_position) < ALT_FIRE_RANGE {
+            action_state.press(Action::AltFire);
+        }
+    }
+}
+
+/// Starts a run straight from the main menu
+fn bot_start_game(
+    settings: Res<BotSettings>,
+    mut state: ResMut<State<GameState>>,
+    mut asset_keys: Option<ResMut<DynamicAssets>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    start_run(&mut state, asset_keys.as_deref_mut(), false);
+}
+
+/// Records the run that just ended and starts the next one
+fn bot_finish_run(
+    settings: Res<BotSettings>,
+    mut state: ResMut<State<GameState>>,
+    mut report: ResMut<BotReport>,
+    mut reset_events: EventWriter<ResetRun>,
+    mut app_exit_events: EventWriter<AppExit>,
+    scoreboards: Query<&Scoreboard>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    if let Some(scoreboard) = scoreboards.iter().next() {
+        let result = RunResult {
+            time_survived: scoreboard.time_survived,
+            level: scoreboard.level,
+            score: scoreboard.score(),
+        };
+        info!(
+            "Bot run {}: survived {:.1}s, reached level {}, scored {}",
+            report.runs.len() + 1,
+            result.time_survived,
+            result.level,
+            result.score
+        );
+        report.runs.push(result);
+    }
+    if settings.runs > 0 && report.runs.len() >= settings.runs {
+        report.print_summary();
+        app_exit_events.send(AppExit);
+        return;
+    }
+    reset_events.send(ResetRun);
+    state.pop().expect("Failed to change state");
+}
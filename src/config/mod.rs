@@ -0,0 +1,289 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use ron::{ser::PrettyConfig, value::Map, Value};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    input::{actions::InputBinding, Action, ActionMap},
+    player::{camera_effects::CameraEffectSettings, MovementSettings},
+    run::{launch::LaunchOptions, replay::ReplayPlayer},
+    ui::menu::GamePreferences,
+};
+
+/// Bump when saved settings change shape and add a step to [`migrate`]
+const CONFIG_VERSION: u32 = 1;
+const CONFIG_FILE: &str = "settings.ron";
+
+/// Loads preferences, movement settings, camera effects and bindings at startup and saves them
+/// whenever they change
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SavedConfig {
+            path: config_path(),
+            text: String::new(),
+            preferences: GamePreferences::default(),
+        })
+        .add_startup_system(load_config)
+        .add_startup_system(force_preferences.after(load_config))
+        .add_system(save_config);
+    }
+}
+
+/// Where the settings live and what was last written there
+struct SavedConfig {
+    path: Option<PathBuf>,
+    text: String,
+    /// The player's own preferences, written in place of ones forced from the command line
+    preferences: GamePreferences,
+}
+
+#[derive(Serialize)]
+struct ConfigFile<'a> {
+    version: u32,
+    preferences: &'a GamePreferences,
+    movement: &'a MovementSettings,
+    camera_effects: &'a CameraEffectSettings,
+    /// Bindings are written as strings so a single bad one can be skipped on load
+    bindings: BTreeMap<String, Vec<String>>,
+}
+
+fn config_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "confluence-of-futility")
+        .map(|dirs| dirs.config_dir().join(CONFIG_FILE))
+}
+
+fn config_text(
+    preferences: &GamePreferences,
+    movement: &MovementSettings,
+    camera_effects: &CameraEffectSettings,
+    action_map: &ActionMap,
+) -> Option<String> {
+    let bindings = Action::all()
+        .into_iter()
+        .filter_map(|action| {
+            let bindings = action_map
+                .bindings(action)
+                .iter()
+                .filter_map(|binding| ron::to_string(binding).ok())
+                .collect();
+            ron::to_string(&action)
+                .ok()
+                .map(|action| (action, bindings))
+        })
+        .collect();
+    let file = ConfigFile {
+        version: CONFIG_VERSION,
+        preferences,
+        movement,
+        camera_effects,
+        bindings,
+    };
+    ron::ser::to_string_pretty(&file, PrettyConfig::new()).ok()
+}
+
+fn load_config(
+    mut saved: ResMut<SavedConfig>,
+    mut preferences: ResMut<GamePreferences>,
+    mut movement: ResMut<MovementSettings>,
+    mut camera_effects: ResMut<CameraEffectSettings>,
+    mut action_map: ResMut<ActionMap>,
+) {
+    let path = match &saved.path {
+        Some(path) => path,
+        None => {
+            warn!("No config directory found, settings will not be saved");
+            return;
+        }
+    };
+    // A missing file just means the defaults are used until something changes
+    if let Ok(text) = fs::read_to_string(path) {
+        match parse_config(&text) {
+            Some(mut root) => {
+                *preferences = merge(root.remove(&key("preferences")), "preferences");
+                *movement = merge(root.remove(&key("movement")), "movement");
+                *camera_effects = merge(root.remove(&key("camera_effects")), "camera_effects");
+                *action_map = load_bindings(root.remove(&key("bindings")));
+                info!("Loaded settings from {}", path.display());
+            }
+            None => warn!("Could not read {}, using default settings", path.display()),
+        }
+    }
+    if let Some(text) = config_text(&preferences, &movement, &camera_effects, &action_map) {
+        saved.text = text;
+    }
+    saved.preferences = preferences.clone();
+}
+
+/// Swaps in the preferences given on the command line, like `--potato`
+fn force_preferences(launch: Res<LaunchOptions>, mut preferences: ResMut<GamePreferences>) {
+    if let Some(forced) = &launch.preferences {
+        *preferences = forced.clone();
+    }
+}
+
+fn save_config(
+    mut saved: ResMut<SavedConfig>,
+    preferences: Res<GamePreferences>,
+    movement: Res<MovementSettings>,
+    camera_effects: Res<CameraEffectSettings>,
+    action_map: Res<ActionMap>,
+    replay_player: Res<ReplayPlayer>,
+    launch: Res<LaunchOptions>,
+) {
+    if !preferences.is_changed()
+        && !movement.is_changed()
+        && !camera_effects.is_changed()
+        && !action_map.is_changed()
+    {
+        return;
+    }
+    // A replay brings its own movement settings, the player's come back when it's over
+    if replay_player.borrows_settings() {
+        return;
+    }
+    // Preferences forced from the command line are only for this launch
+    if launch.preferences.is_none() {
+        saved.preferences = preferences.clone();
+    }
+    // The menus borrow settings mutably every frame, so only write when something really changed
+    let text = match config_text(&saved.preferences, &movement, &camera_effects, &action_map) {
+        Some(text) if text != saved.text => text,
+        _ => return,
+    };
+    if let Some(path) = &saved.path {
+        let written = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(path, &text)),
+            None => fs::write(path, &text),
+        };
+        if let Err(err) = written {
+            warn!("Could not save settings to {}: {}", path.display(), err);
+        }
+    }
+    saved.text = text;
+}
+
+fn key(name: &str) -> Value {
+    Value::String(name.to_string())
+}
+
+/// Reads the top level of the file and brings it up to [`CONFIG_VERSION`]
+fn parse_config(text: &str) -> Option<Map> {
+    let mut root = match ron::from_str(text) {
+        Ok(Value::Map(root)) => root,
+        _ => return None,
+    };
+    let version = root
+        .remove(&key("version"))
+        .and_then(|version| version.into_rust::<u32>().ok())
+        .unwrap_or(0);
+    if version > CONFIG_VERSION {
+        warn!(
+            "Settings are from a newer version ({}), loading what still fits",
+            version
+        );
+    }
+    migrate(version, &mut root);
+    Some(root)
+}
+
+/// Upgrades settings saved by older versions one step at a time
+fn migrate(mut version: u32, root: &mut Map) {
+    while version < CONFIG_VERSION {
+        if version == 0 {
+            // Written before the file was versioned, one sensitivity covered both axes
+            if let Some(Value::Map(mut movement)) = root.remove(&key("movement")) {
+                if let Some(sensitivity) = movement.remove(&key("sensitivity")) {
+                    movement.insert(key("horizontal_sensitivity"), sensitivity.clone());
+                    movement.insert(key("vertical_sensitivity"), sensitivity);
+                }
+                root.insert(key("movement"), Value::Map(movement));
+            }
+        }
+        version += 1;
+    }
+}
+
+/// Starts from the defaults and takes every saved field that still fits, so one bad value
+/// doesn't throw away the rest
+fn merge<T: Serialize + DeserializeOwned + Default>(saved: Option<Value>, section: &str) -> T {
+    let mut merged = match ron::to_string(&T::default())
+        .ok()
+        .and_then(|text| ron::from_str(&text).ok())
+    {
+        Some(Value::Map(merged)) => merged,
+        _ => return T::default(),
+    };
+    if let Some(Value::Map(saved)) = saved {
+        for (field, value) in saved.iter() {
+            if !merged.keys().any(|known| known == field) {
+                warn!("Ignoring unknown setting {}.{:?}", section, field);
+                continue;
+            }
+            let mut candidate = merged.clone();
+            candidate.insert(field.clone(), value.clone());
+            if Value::Map(candidate.clone()).into_rust::<T>().is_ok() {
+                merged = candidate;
+            } else {
+                warn!("Ignoring invalid setting {}.{:?}", section, field);
+            }
+        }
+    }
+    Value::Map(merged).into_rust().unwrap_or_default()
+}
+
+/// Replaces the default bindings of every action found in the file, skipping anything unreadable
+fn load_bindings(saved: Option<Value>) -> ActionMap {
+    let mut action_map = ActionMap::default();
+    let saved = match saved {
+        Some(Value::Map(saved)) => saved,
+        _ => return action_map,
+    };
+    let mut loaded = Vec::new();
+    for (action, bindings) in saved.iter() {
+        let action = match action
+            .clone()
+            .into_rust::<String>()
+            .ok()
+            .and_then(|action| ron::from_str::<Action>(&action).ok())
+        {
+            Some(action) => action,
+            None => {
+                warn!("Ignoring bindings for unknown action {:?}", action);
+                continue;
+            }
+        };
+        let bindings = bindings
+            .clone()
+            .into_rust::<Vec<String>>()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|binding| match ron::from_str::<InputBinding>(binding) {
+                Ok(binding) => Some(binding),
+                Err(_) => {
+                    warn!("Ignoring unknown binding {} for {:?}", binding, action);
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        loaded.push((action, bindings));
+    }
+    // Clear first so a binding moved between actions doesn't clash with the default it replaced
+    for (action, _) in &loaded {
+        action_map.clear(*action);
+    }
+    for (action, bindings) in loaded {
+        for binding in bindings {
+            if let Err(other) = action_map.bind(action, binding) {
+                warn!(
+                    "Ignoring binding {} for {:?}, it is already used by {:?}",
+                    binding, action, other
+                );
+            }
+        }
+    }
+    action_map
+}
@@ -6,16 +6,20 @@ use heron::{
 };
 
 use crate::{
-    assets::AudioAssets,
-    player::{Player, PlayerEvent},
+    player::{HitKind, Player, PlayerEvent},
+    run::RunScoped,
     Layer,
 };
 
-use bevy_kira_audio::Audio;
+use super::EnemyEvent;
+
+/// Meters per second on Normal difficulty
+pub const BULLET_SPEED: f32 = 80.0;
 
 #[derive(Bundle)]
 pub struct BulletBundle {
     bullet: Bullet,
+    run_scoped: RunScoped,
     transform: Transform,
     global_transform: GlobalTransform,
     rigid_body: RigidBody,
@@ -26,12 +30,14 @@ pub struct BulletBundle {
 }
 
 impl BulletBundle {
-    pub fn shoot(from: Vec3, direction: Vec3, damage: f32, splash_radius: f32) -> Self {
+    pub fn shoot(from: Vec3, direction: Vec3, speed: f32, damage: f32, splash_radius: f32) -> Self {
         BulletBundle {
             bullet: Bullet {
                 damage,
                 splash_radius,
+                origin: from,
             },
+            run_scoped: RunScoped,
             transform: Transform::from_translation(from).looking_at(direction, Vec3::Y),
             global_transform: GlobalTransform::default(),
             rigid_body: RigidBody::Dynamic,
@@ -39,7 +45,7 @@ impl BulletBundle {
             collision_layers: CollisionLayers::none()
                 .with_group(Layer::Bullet)
                 .with_masks([Layer::World, Layer::Player]),
-            velocity: Velocity::from_linear(direction * 80.0),
+            velocity: Velocity::from_linear(direction * speed),
             physic_material: PhysicMaterial {
                 // density: 0.001,
                 ..Default::default()
@@ -52,6 +58,8 @@ impl BulletBundle {
 pub struct Bullet {
     damage: f32,
     splash_radius: f32,
+    /// Where the bullet was fired from
+    origin: Vec3,
 }
 
 pub fn disable_gravity_for_bullets(
@@ -72,8 +80,7 @@ pub fn handle_bullet_collisions(
     mut players: Query<(Entity, &Transform, &mut Player)>,
     mut player_events: EventWriter<PlayerEvent>,
     bullets: Query<(&Transform, &Bullet)>,
-    audio: Res<Audio>,
-    audio_assets: Res<AudioAssets>,
+    mut enemy_events: EventWriter<EnemyEvent>,
 ) {
     for collision in collision_events.iter() {
         match collision {
@@ -86,17 +93,24 @@ pub fn handle_bullet_collisions(
                     continue;
                 };
 
-                // TODO use event
-                audio.play(audio_assets.get_unit2_projectile_collide().clone());
-
                 let (bullet_ent, other_ent) =
                     (bullet.rigid_body_entity(), other.rigid_body_entity());
 
                 if let Ok((bullet_transform, bullet)) = bullets.get(bullet_ent) {
+                    enemy_events.send(EnemyEvent::BulletHit {
+                        position: bullet_transform.translation,
+                    });
                     if is_player(other) {
-                        if let Ok((_, _, mut player)) = players.get_mut(other_ent) {
-                            player_events.send(PlayerEvent::Hit { laser: false });
-                            player.health -= bullet.damage;
+                        if let Ok((_, player_transform, mut player)) = players.get_mut(other_ent) {
+                            player_events.send(PlayerEvent::Hit {
+                                kind: HitKind::Projectile,
+                                source: bullet.origin,
+                                direction: (player_transform.translation - bullet.origin)
+                                    .normalize_or_zero(),
+                            });
+                            if player.take_damage(bullet.damage) {
+                                player_events.send(PlayerEvent::ShieldBroken);
+                            }
                         }
                     } else if let Some((_entity, player_transform, mut player)) =
                         players.iter_mut().next()
@@ -107,8 +121,16 @@ pub fn handle_bullet_collisions(
                             < bullet.splash_radius
                         //Splash Damage
                         {
-                            player_events.send(PlayerEvent::Hit { laser: false });
-                            player.health -= bullet.damage * 0.5;
+                            player_events.send(PlayerEvent::Hit {
+                                kind: HitKind::Splash,
+                                source: bullet_transform.translation,
+                                direction: (player_transform.translation
+                                    - bullet_transform.translation)
+                                    .normalize_or_zero(),
+                            });
+                            if player.take_damage(bullet.damage * 0.5) {
+                                player_events.send(PlayerEvent::ShieldBroken);
+                            }
                         }
                     }
                 }
@@ -1,88 +1,46 @@
 use bevy::prelude::*;
-use bevy_polyline::{Polyline, PolylineBundle, PolylineMaterial};
-use heron::{
-    rapier_plugin::PhysicsWorld, CollisionLayers, CollisionShape, PhysicMaterial, PhysicsLayer,
-    RigidBody,
-};
+use heron::{rapier_plugin::PhysicsWorld, CollisionLayers};
 
 use crate::{
-    assets::ModelAssets,
-    player::{Player, PlayerEvent},
-    Layer,
+    modes::difficulty::Difficulty,
+    player::{HitKind, Player, PlayerEvent},
+    GameClock, Layer,
 };
 
-use super::{Alive, Dead, EnemiesState, Enemy, EnemyBehaviour, EnemyLastFired};
+use super::{Alive, Dead, EnemiesState, Enemy, EnemyLastFired};
 
 #[derive(Component, Default)]
 pub struct LaserieEnemy;
 
-impl EnemyBehaviour for LaserieEnemy {
-    fn spawn(commands: &mut Commands, transform: Transform, model_assets: &ModelAssets) -> Entity {
-        commands
-            .spawn_bundle((transform, GlobalTransform::default()))
-            .insert(RigidBody::Dynamic)
-            .insert(CollisionShape::Sphere { radius: 2.7 })
-            .insert(CollisionLayers::from_bits(
-                Layer::Enemy.to_bits(),
-                Layer::all_bits(),
-            ))
-            .insert(PhysicMaterial {
-                density: 1.0, // Value must be greater than 0.0
-                ..Default::default()
-            })
-            .insert(EnemyLastFired(Timer::from_seconds(0.9, true)))
-            .insert(Enemy {
-                health: 500,
-                range: 100.0,
-                update_destination_timer: Timer::from_seconds(2.0, true),
-                move_speed: 38.0,
-                weapon_damage: 15.0,
-                weapon_splash_radius: 0.0,
-                rotate_lerp: 0.3,
-                ..Default::default()
-            })
-            .insert(LaserieEnemy)
-            .insert(Alive)
-            .with_children(|parent| {
-                parent.spawn_scene(model_assets.unit1.clone());
-            })
-            .id()
-    }
-}
+/// Where a Laserie's beam ends, in the enemy's own space. Zero while the laser is off.
+#[derive(Component, Default)]
+pub struct LaserBeam(pub Vec3);
 
 pub fn laserie_enemies_fire_at_player(
     //mut commands: Commands,
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut enemies: Query<
-        (&Transform, &mut EnemyLastFired, &mut Enemy, &Children),
+        (&Transform, &mut EnemyLastFired, &mut Enemy, &mut LaserBeam),
         (With<Alive>, With<LaserieEnemy>),
     >,
     //enemies_state: Res<EnemiesState>,
     //audio: Res<Audio>,
     //audio_assets: Res<AudioAssets>,
     mut players: Query<&mut Player>,
-    mut polylines: ResMut<Assets<Polyline>>,
     physics_world: PhysicsWorld,
-    spawned_polys: Query<&Handle<Polyline>>,
     mut player_events: EventWriter<PlayerEvent>,
     enemies_state: Res<EnemiesState>,
+    difficulty: Res<Difficulty>,
 ) {
     if let Some(player) = players.iter().next() {
         if player.health <= 0.0 {
-            for (.., children) in enemies.iter_mut() {
-                for &child in children.iter() {
-                    if let Ok(polyline_h) = spawned_polys.get(child) {
-                        if let Some(polyline) = polylines.get_mut(polyline_h) {
-                            polyline.vertices[0] = Vec3::ZERO;
-                            polyline.vertices[1] = Vec3::ZERO;
-                        }
-                    }
-                }
+            for (.., mut beam) in enemies.iter_mut() {
+                beam.0 = Vec3::ZERO;
             }
             return;
         }
-        for (transform, mut enemy_last_fired, enemy, children) in enemies.iter_mut() {
-            enemy_last_fired.0.tick(time.delta()); //enemy_last_fired.0.just_finished() &&
+        for (transform, mut enemy_last_fired, enemy, mut beam) in enemies.iter_mut() {
+            enemy_last_fired.0.tick(clock.delta()); //enemy_last_fired.0.just_finished() &&
             if enemy.within_range_of_player {
                 // Shoot at player
 
@@ -98,10 +56,17 @@ pub fn laserie_enemies_fire_at_player(
                 ) {
                     // TODO move to be triggered by event
                     if let Ok(mut player) = players.get_mut(collision.entity) {
-                        player.health -= enemy.weapon_damage
-                            * time.delta_seconds()
-                            * enemies_state.get_level_params().damage_multiplier;
-                        player_events.send(PlayerEvent::Hit { laser: true });
+                        let damage = enemy.weapon_damage
+                            * clock.delta_seconds()
+                            * enemies_state.level_params(*difficulty).damage_multiplier;
+                        player_events.send(PlayerEvent::Hit {
+                            kind: HitKind::Laser,
+                            source: transform.translation,
+                            direction: transform.forward(),
+                        });
+                        if player.take_damage(damage) {
+                            player_events.send(PlayerEvent::ShieldBroken);
+                        }
                     } else {
                         endpoint = Vec3::new(
                             0.5,
@@ -111,14 +76,7 @@ pub fn laserie_enemies_fire_at_player(
                     }
                 }
 
-                for &child in children.iter() {
-                    if let Ok(polyline_h) = spawned_polys.get(child) {
-                        if let Some(polyline) = polylines.get_mut(polyline_h) {
-                            polyline.vertices[0] = Vec3::ZERO;
-                            polyline.vertices[1] = endpoint;
-                        }
-                    }
-                }
+                beam.0 = endpoint;
                 // TODO use event
                 //audio.play(audio_assets.get_unit2_fire().clone());
             }
@@ -126,67 +84,13 @@ pub fn laserie_enemies_fire_at_player(
     }
 }
 
-#[derive(Component)]
-pub struct HasLaser;
-//TODO make part of initially creating laserie
-pub fn add_lasers_to_laserie(
-    mut commands: Commands,
-    enemies: Query<
-        Entity,
-        (
-            Without<Player>,
-            With<Alive>,
-            With<Enemy>,
-            With<LaserieEnemy>,
-            Without<HasLaser>,
-        ),
-    >,
-    mut polylines: ResMut<Assets<Polyline>>,
-    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
-) {
-    for entity in enemies.iter() {
-        commands
-            .entity(entity)
-            .with_children(|parent| {
-                parent.spawn_bundle(PolylineBundle {
-                    polyline: polylines.add(Polyline {
-                        vertices: vec![Vec3::ZERO, Vec3::ZERO],
-                    }),
-                    material: polyline_materials.add(PolylineMaterial {
-                        width: 20.0,
-                        color: Color::rgba(1.0, 0.0, 1.0, 0.9),
-                        perspective: true,
-                    }),
-                    visibility: Visibility { is_visible: true },
-                    ..Default::default()
-                });
-            })
-            .insert(HasLaser);
-    }
-}
-
 pub fn turn_off_dead_laser(
-    enemies: Query<
-        &Children,
-        (
-            Without<Player>,
-            With<Dead>,
-            With<HasLaser>,
-            With<Enemy>,
-            With<LaserieEnemy>,
-        ),
+    mut enemies: Query<
+        &mut LaserBeam,
+        (Without<Player>, With<Dead>, With<Enemy>, With<LaserieEnemy>),
     >,
-    spawned_polys: Query<&Handle<Polyline>>,
-    mut polylines: ResMut<Assets<Polyline>>,
 ) {
-    for children in enemies.iter() {
-        for &child in children.iter() {
-            if let Ok(polyline_h) = spawned_polys.get(child) {
-                if let Some(polyline) = polylines.get_mut(polyline_h) {
-                    polyline.vertices[0] = Vec3::ZERO;
-                    polyline.vertices[1] = Vec3::ZERO;
-                }
-            }
-        }
+    for mut beam in enemies.iter_mut() {
+        beam.0 = Vec3::ZERO;
     }
 }
@@ -6,39 +6,34 @@ use std::{
 
 use bevy::prelude::*;
 
-use bevy_polyline::{Polyline, PolylineBundle, PolylineMaterial};
 use heron::{
     rapier_plugin::{convert::IntoRapier, rapier3d::prelude::RigidBodySet, RigidBodyHandle},
     CollisionLayers, CollisionShape, PhysicMaterial, PhysicsLayer, RigidBody,
 };
 use pathfinding::directed::astar::astar;
 use rand::{prelude::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 use splines::{Interpolation, Spline};
 
 use crate::{
-    assets::{
-        orb_material::{OrbMaterial, OrbProperties},
-        AudioAssets, GameState, ModelAssets,
-    },
+    assets::GameState,
+    modes::{difficulty::Difficulty, GameMode, ModeState},
     player::{Player, PlayerEvent},
-    ui::{menu::GamePreferences, scoreboard::ScoreboardEvent},
-    Layer,
+    run::{GameRng, RunAppExt, RunScoped},
+    ui::scoreboard::ScoreboardEvent,
+    GameClock, GameplaySystem, Layer,
 };
 
 use self::{
     bullet::{disable_gravity_for_bullets, handle_bullet_collisions},
-    laserie::{
-        add_lasers_to_laserie, laserie_enemies_fire_at_player, turn_off_dead_laser, HasLaser,
-        LaserieEnemy,
-    },
+    laserie::{laserie_enemies_fire_at_player, turn_off_dead_laser, LaserBeam, LaserieEnemy},
     orbie::{orbie_enemies_fire_at_player, OrbieEnemy},
 };
 
-use bevy_kira_audio::Audio;
-
-mod bullet;
+pub mod bullet;
 pub mod laserie;
 mod orbie;
+pub mod visuals;
 
 #[derive(Default)]
 pub struct Waypoints {
@@ -53,39 +48,41 @@ pub struct EnemiesPlugin;
 impl Plugin for EnemiesPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Waypoints::default())
-            .insert_resource(EnemiesState::default())
-            .insert_resource(EnemySpawnTimer({
-                //spawn_enemies_on_timer will set the duration back to 1 when the timer triggers
-                let mut timer = Timer::from_seconds(1.0, true);
-                timer.pause();
-                timer
-            }))
+            .add_event::<EnemyDamaged>()
+            .add_event::<EnemyEvent>()
+            .init_run_resource::<EnemiesState>()
+            .init_run_resource::<EnemySpawnTimer>()
             //.insert_resource(WaypointTimer(Timer::from_seconds(5.0, false)))
-            .insert_resource(UpdateDestinationsTimer(Timer::from_seconds(2.0, true)))
+            .init_run_resource::<UpdateDestinationsTimer>()
             .add_system_set(SystemSet::on_enter(GameState::Playing))
             .add_system_set(
                 SystemSet::on_update(GameState::Playing)
-                    .with_system(enemies_look_at)
-                    .with_system(orbie_enemies_fire_at_player)
-                    .with_system(laserie_enemies_fire_at_player)
-                    .with_system(handle_bullet_collisions)
+                    .label(GameplaySystem::Enemies)
+                    .after(GameplaySystem::Player)
+                    .with_system(update_destinations)
+                    .with_system(enemies_update_current_destination.after(update_destinations))
+                    .with_system(
+                        enemies_move_to_destination
+                            .after(enemies_update_current_destination)
+                            .after(disable_gravity_for_bullets),
+                    )
+                    .with_system(enemies_look_at.after(enemies_move_to_destination))
+                    .with_system(orbie_enemies_fire_at_player.after(enemies_look_at))
+                    .with_system(laserie_enemies_fire_at_player.after(orbie_enemies_fire_at_player))
+                    .with_system(handle_bullet_collisions.after(laserie_enemies_fire_at_player))
                     .with_system(disable_gravity_for_bullets)
                     //.with_system(waypoint_debug)
-                    .with_system(spawn_enemies_on_timer)
-                    .with_system(update_destinations)
-                    .with_system(enemies_update_current_destination)
-                    .with_system(enemies_move_to_destination)
-                    .with_system(kill_enemy)
-                    .with_system(progress_explosions)
+                    .with_system(kill_enemy.after(handle_bullet_collisions))
+                    .with_system(handle_scoreboard_event.after(kill_enemy))
+                    .with_system(spawn_enemies_on_timer.after(handle_scoreboard_event))
                     .with_system(clean_up_dead)
-                    .with_system(player_takes_damage)
-                    .with_system(add_lasers_to_laserie)
-                    .with_system(turn_off_dead_laser)
-                    .with_system(handle_scoreboard_event),
+                    .with_system(player_takes_damage.after(kill_enemy))
+                    .with_system(turn_off_dead_laser.after(laserie_enemies_fire_at_player)),
             );
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EnemiesState {
     pub enemies_killed: u32,
     pub current_level: usize,
@@ -94,6 +91,7 @@ pub struct EnemiesState {
     pub last_time_player_took_damage: f32,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LevelParams {
     kills_to_level_up: usize,
     max_enemies: usize,
@@ -141,18 +139,36 @@ impl EnemiesState {
     pub fn get_level_params(&self) -> &LevelParams {
         &self.levels[self.current_level.min(self.levels.len() - 1)]
     }
+
+    /// The current level's damage and enemy count with the difficulty applied
+    pub fn level_params(&self, difficulty: Difficulty) -> LevelParams {
+        let params = self.get_level_params();
+        LevelParams {
+            kills_to_level_up: params.kills_to_level_up,
+            max_enemies: ((params.max_enemies as f32 * difficulty.enemy_count_scale()).round()
+                as usize)
+                .max(1),
+            damage_multiplier: params.damage_multiplier * difficulty.damage_scale(),
+        }
+    }
 }
 
 struct UpdateDestinationsTimer(Timer);
 
+impl Default for UpdateDestinationsTimer {
+    fn default() -> Self {
+        UpdateDestinationsTimer(Timer::from_seconds(2.0, true))
+    }
+}
+
 fn update_destinations(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut timer: ResMut<UpdateDestinationsTimer>,
     mut enemies_state: ResMut<EnemiesState>,
     waypoints: Res<Waypoints>,
     players: Query<&Transform, With<Player>>,
 ) {
-    timer.0.tick(time.delta());
+    timer.0.tick(clock.delta());
     if !timer.0.just_finished() {
         return;
     }
@@ -163,7 +179,7 @@ fn update_destinations(
             distances.push((player_transform.translation.distance(*loc), i));
         }
         distances.sort_by(|a, b| (a.0).partial_cmp(&b.0).unwrap());
-        if time.seconds_since_startup() as f32 - enemies_state.last_time_player_took_damage > 5.0 {
+        if clock.seconds() - enemies_state.last_time_player_took_damage > 5.0 {
             //Pick the closest waypoints if we haven't hit the player in a while
             enemies_state.destinations[0] = distances[0].1;
             enemies_state.destinations[1] = distances[1].1;
@@ -178,46 +194,54 @@ fn update_destinations(
 
 pub struct EnemySpawnTimer(pub Timer);
 
+impl Default for EnemySpawnTimer {
+    /// Paused until the player first fires
+    fn default() -> Self {
+        //spawn_enemies_on_timer will set the duration back to 1 when the timer triggers
+        let mut timer = Timer::from_seconds(1.0, true);
+        timer.pause();
+        EnemySpawnTimer(timer)
+    }
+}
+
 fn spawn_enemies_on_timer(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut timer: ResMut<EnemySpawnTimer>,
     mut commands: Commands,
-    model_assets: Res<ModelAssets>,
+    mut rng: ResMut<GameRng>,
     waypoints: Res<Waypoints>,
     enemies_state: Res<EnemiesState>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    mut mode_state: ResMut<ModeState>,
     enemies: Query<&Transform, (With<Enemy>, Without<Player>, With<Alive>)>,
 ) {
-    timer.0.tick(time.delta());
+    timer.0.tick(clock.delta());
     if !timer.0.just_finished() {
         return;
     }
     if timer.0.duration().as_secs_f32() > 1.0 {
         timer.0.set_duration(Duration::from_secs(1));
     }
-    if enemies.iter().count() >= enemies_state.get_level_params().max_enemies {
-        return;
-    }
+    let max_enemies = enemies_state.level_params(*difficulty).max_enemies;
+    let kind = match mode.next_spawn(&mode_state, max_enemies, enemies.iter().count(), &mut rng.0) {
+        Some(kind) => kind,
+        None => return,
+    };
     //Try 3 times to spawn an enemy where there is enough space
     'outer: for _ in 0..3 {
-        let spawn_point = waypoints.outfront.choose(&mut rand::thread_rng()).unwrap();
+        let spawn_point = waypoints.outfront.choose(&mut rng.0).unwrap();
         for enemy in enemies.iter() {
             if spawn_point.distance(enemy.translation) < 5.0 {
                 continue 'outer;
             }
         }
-        if rand::thread_rng().gen_range(0..=1) == 0 {
-            LaserieEnemy::spawn(
-                &mut commands,
-                Transform::from_xyz(spawn_point.x, spawn_point.y, spawn_point.z),
-                &model_assets,
-            );
-        } else {
-            OrbieEnemy::spawn(
-                &mut commands,
-                Transform::from_xyz(spawn_point.x, spawn_point.y, spawn_point.z),
-                &model_assets,
-            );
-        }
+        spawn_enemy(
+            &mut commands,
+            kind,
+            Transform::from_xyz(spawn_point.x, spawn_point.y, spawn_point.z),
+        );
+        mode_state.spawned();
         return;
     }
 }
@@ -335,6 +359,24 @@ impl Hash for WaypointForPathfinding {
     }
 }
 
+/// Sent when the player's weapons damage an enemy
+pub struct EnemyDamaged {
+    pub entity: Entity,
+    pub amount: i32,
+}
+
+/// Things enemies do that are seen or heard, but don't change the simulation
+pub enum EnemyEvent {
+    /// An Orbie fired a bullet
+    Fired { position: Vec3 },
+    /// A bullet hit something and was removed
+    BulletHit { position: Vec3 },
+    Killed {
+        kind: EnemyKind,
+        transform: Transform,
+    },
+}
+
 #[derive(Component)]
 pub struct Enemy {
     pub health: i32,
@@ -342,6 +384,8 @@ pub struct Enemy {
     range: f32,
     current_destination: usize,
     current_random_offset: Vec3,
+    /// Where around the player this enemy aims, see [`Difficulty::aim_error`]
+    aim_offset: Vec3,
     big: bool,
     update_destination_timer: Timer,
     move_speed: f32,
@@ -360,6 +404,7 @@ impl Default for Enemy {
             update_destination_timer: Timer::from_seconds(2.0, true),
             move_speed: 30.0,
             current_random_offset: Vec3::new(0.0, 0.0, 0.0),
+            aim_offset: Vec3::ZERO,
             weapon_damage: 40.0,
             weapon_splash_radius: 8.0,
             rotate_lerp: 0.04,
@@ -368,37 +413,187 @@ impl Default for Enemy {
     }
 }
 
+/// Enemy types as counted on the scoreboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum EnemyKind {
+    Orbie,
+    Laserie,
+    BigOrbie,
+    BigLaserie,
+}
+
+impl EnemyKind {
+    pub fn all() -> [EnemyKind; 4] {
+        [
+            EnemyKind::Orbie,
+            EnemyKind::Laserie,
+            EnemyKind::BigOrbie,
+            EnemyKind::BigLaserie,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EnemyKind::Orbie => "Orbie",
+            EnemyKind::Laserie => "Laserie",
+            EnemyKind::BigOrbie => "Big Orbie",
+            EnemyKind::BigLaserie => "Big Laserie",
+        }
+    }
+
+    pub fn of(enemy: &Enemy, is_orbie: bool) -> EnemyKind {
+        match (is_orbie, enemy.big) {
+            (true, false) => EnemyKind::Orbie,
+            (true, true) => EnemyKind::BigOrbie,
+            (false, false) => EnemyKind::Laserie,
+            (false, true) => EnemyKind::BigLaserie,
+        }
+    }
+
+    pub fn is_orbie(&self) -> bool {
+        matches!(self, EnemyKind::Orbie | EnemyKind::BigOrbie)
+    }
+
+    /// Bosses are spawned at three times the size of regular enemies
+    pub fn is_big(&self) -> bool {
+        matches!(self, EnemyKind::BigOrbie | EnemyKind::BigLaserie)
+    }
+
+    fn stats(&self) -> Enemy {
+        match self {
+            EnemyKind::Orbie => Enemy {
+                health: 1000,
+                range: 100.0,
+                move_speed: 30.0,
+                weapon_damage: 40.0,
+                weapon_splash_radius: 8.0,
+                rotate_lerp: 0.9,
+                ..Default::default()
+            },
+            EnemyKind::Laserie => Enemy {
+                health: 500,
+                range: 100.0,
+                move_speed: 38.0,
+                weapon_damage: 15.0,
+                weapon_splash_radius: 0.0,
+                rotate_lerp: 0.3,
+                ..Default::default()
+            },
+            EnemyKind::BigOrbie => Enemy {
+                health: 6000,
+                range: 200.0,
+                move_speed: 15.0,
+                weapon_damage: 60.0,
+                weapon_splash_radius: 12.0,
+                rotate_lerp: 0.1,
+                big: true,
+                ..Default::default()
+            },
+            EnemyKind::BigLaserie => Enemy {
+                health: 5000,
+                range: 180.0,
+                move_speed: 25.0,
+                weapon_damage: 30.0,
+                weapon_splash_radius: 0.0,
+                rotate_lerp: 0.3,
+                big: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn collision_shape(&self) -> CollisionShape {
+        let radius = match self {
+            EnemyKind::BigOrbie => 2.7 * 3.0,
+            _ => 2.7,
+        };
+        CollisionShape::Sphere { radius }
+    }
+
+    fn density(&self) -> f32 {
+        match self {
+            EnemyKind::BigOrbie => 2.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Everything an enemy needs apart from its model, `transform` is scaled up for bosses
+fn enemy_bundle(kind: EnemyKind, transform: Transform) -> impl Bundle {
+    let transform = if kind.is_big() {
+        transform.with_scale(Vec3::new(3.0, 3.0, 3.0))
+    } else {
+        transform
+    };
+    (
+        transform,
+        GlobalTransform::default(),
+        RunScoped,
+        RigidBody::Dynamic,
+        kind.collision_shape(),
+        CollisionLayers::from_bits(Layer::Enemy.to_bits(), Layer::all_bits()),
+        PhysicMaterial {
+            density: kind.density(), // Value must be greater than 0.0
+            ..Default::default()
+        },
+        EnemyLastFired(Timer::from_seconds(0.9, true)),
+        kind.stats(),
+        Alive,
+    )
+}
+
+pub fn spawn_enemy(commands: &mut Commands, kind: EnemyKind, transform: Transform) -> Entity {
+    let mut enemy = commands.spawn_bundle(enemy_bundle(kind, transform));
+    if kind.is_orbie() {
+        enemy.insert(OrbieEnemy);
+    } else {
+        enemy.insert_bundle((LaserieEnemy, LaserBeam::default()));
+    }
+    enemy.id()
+}
+
+/// Like [`spawn_enemy`], for code with direct access to the world
+pub fn spawn_enemy_in_world(world: &mut World, kind: EnemyKind, transform: Transform) -> Entity {
+    let mut enemy = world.spawn();
+    enemy.insert_bundle(enemy_bundle(kind, transform));
+    if kind.is_orbie() {
+        enemy.insert(OrbieEnemy);
+    } else {
+        enemy.insert_bundle((LaserieEnemy, LaserBeam::default()));
+    }
+    enemy.id()
+}
+
 #[derive(Component)]
 pub struct EnemyLastFired(Timer);
 
 #[derive(Component)]
 pub struct Alive;
 
+/// Seconds a dead enemy keeps tumbling around before it's despawned
+const CORPSE_LIFETIME: f32 = 12.0;
+
 #[derive(Component)]
 pub struct Dead {
-    time_to_despawn: f32,
-}
-
-trait EnemyBehaviour {
-    fn spawn(commands: &mut Commands, transform: Transform, model_assets: &ModelAssets) -> Entity;
+    /// [`GameClock`] seconds at the time of death
+    pub died_at: f32,
 }
 
 fn enemies_update_current_destination(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut enemies: Query<&mut Enemy, (Without<Player>, With<Alive>)>,
     enemies_state: Res<EnemiesState>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<GameRng>,
 ) {
     for mut enemy in enemies.iter_mut() {
-        enemy.update_destination_timer.tick(time.delta());
+        enemy.update_destination_timer.tick(clock.delta());
         if !enemy.update_destination_timer.just_finished() {
             continue;
         }
-        enemy.current_destination = *enemies_state
-            .destinations
-            .choose(&mut rand::thread_rng())
-            .unwrap();
+        enemy.current_destination = *enemies_state.destinations.choose(&mut rng.0).unwrap();
 
-        let mut rng = rand::thread_rng();
+        let rng = &mut rng.0;
         if enemy.big {
             enemy.current_random_offset.x = 0.0;
             enemy.current_random_offset.y = rng.gen_range(-10.0f32..=0.0f32);
@@ -408,6 +603,11 @@ fn enemies_update_current_destination(
             enemy.current_random_offset.y = rng.gen_range(-20.0f32..=0.0f32);
             enemy.current_random_offset.z = rng.gen_range(-5.0f32..=5.0f32);
         }
+        enemy.aim_offset = Vec3::new(
+            rng.gen_range(-1.0f32..=1.0f32),
+            rng.gen_range(-1.0f32..=1.0f32),
+            rng.gen_range(-1.0f32..=1.0f32),
+        ) * difficulty.aim_error();
     }
 }
 
@@ -451,27 +651,31 @@ fn enemies_move_to_destination(
 }
 
 fn kill_enemy(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut commands: Commands,
     mut rigid_bodies: ResMut<RigidBodySet>,
     mut enemies: Query<
-        (Entity, &mut Transform, &mut Enemy, &RigidBodyHandle),
+        (
+            Entity,
+            &mut Transform,
+            &mut Enemy,
+            &RigidBodyHandle,
+            Option<&OrbieEnemy>,
+        ),
         (Without<Player>, With<Alive>),
     >,
     mut enemies_state: ResMut<EnemiesState>,
-    mut orb_materials: ResMut<Assets<OrbMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    audio: Res<Audio>,
-    audio_assets: Res<AudioAssets>,
+    mut enemy_events: EventWriter<EnemyEvent>,
     mut scoreboard_events: EventWriter<ScoreboardEvent>,
-    preferences: Res<GamePreferences>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
 ) {
-    for (entity, enemy_transform, enemy, rb) in enemies.iter_mut() {
+    for (entity, enemy_transform, enemy, rb, orbie) in enemies.iter_mut() {
         if enemy.health > 0 {
             continue;
         }
         if let Some(body) = rigid_bodies.get_mut(rb.into_rapier()) {
-            let mut rng = rand::thread_rng();
+            let rng = &mut rng.0;
             body.apply_torque_impulse(
                 [
                     rng.gen_range(-2000.0f32..=2000.0f32),
@@ -484,46 +688,19 @@ fn kill_enemy(
             body.apply_impulse([0.0, -1500.0, 0.0].into(), false);
         }
 
-        let orb_material_props = OrbProperties {
-            color_tint: Vec3::new(1.0, 0.95, 0.8),
-            alpha: 1.0,
-            ..Default::default()
-        };
-        let orb_material = orb_materials.add(OrbMaterial {
-            material_properties: orb_material_props,
-            noise_texture: None,
-        });
-        let mesh = meshes.add(Mesh::from(shape::Icosphere {
-            radius: 2.5,
-            subdivisions: 1,
-        })); //TODO use billboard
-        commands
-            .spawn()
-            .insert_bundle(MaterialMeshBundle {
-                mesh,
-                transform: *enemy_transform,
-                material: orb_material.clone(),
-                ..Default::default()
-            })
-            .insert(Explosion {
-                progress: 0.0,
-                speed: 3.2,
-                scale: 0.08,
-                handle: orb_material,
-            });
-
         commands.entity(entity).remove::<Alive>();
 
-        let time_till_despawn = if preferences.potato { 2.0 } else { 12.0 };
         commands.entity(entity).insert(Dead {
-            time_to_despawn: time.seconds_since_startup() as f32 + time_till_despawn,
+            died_at: clock.seconds(),
         });
-        // TODO use event
-        audio.play(audio_assets.get_unit2_explosion().clone());
         enemies_state.enemies_killed += 1;
-        scoreboard_events.send(ScoreboardEvent::Kill);
-        if enemies_state.enemies_killed >= enemies_state.get_level_params().kills_to_level_up as u32
-        {
+        let kind = EnemyKind::of(&enemy, orbie.is_some());
+        enemy_events.send(EnemyEvent::Killed {
+            kind,
+            transform: *enemy_transform,
+        });
+        scoreboard_events.send(ScoreboardEvent::Kill(kind));
+        if mode.levels_up(&enemies_state, kind) {
             enemies_state.current_level =
                 (enemies_state.current_level + 1).min(enemies_state.levels.len() - 1);
             scoreboard_events.send(ScoreboardEvent::LevelUp);
@@ -535,6 +712,7 @@ fn enemies_look_at(
     players: Query<&Transform, With<Player>>,
     mut enemies: Query<(&mut Transform, &mut Enemy), (Without<Player>, With<Alive>)>,
     player: Query<&Player>,
+    difficulty: Res<Difficulty>,
 ) {
     if let Some(player) = player.iter().next() {
         if let Some(player_transform) = players.iter().next() {
@@ -546,16 +724,19 @@ fn enemies_look_at(
                     && player.health > 0.0
                 {
                     enemy.within_range_of_player = true;
-                    let target = enemy_transform
-                        .looking_at(player_transform.translation + Vec3::Y * 1.5, Vec3::Y);
+                    let target = enemy_transform.looking_at(
+                        player_transform.translation + Vec3::Y * 1.5 + enemy.aim_offset,
+                        Vec3::Y,
+                    );
                     // TODO limit to y rotation?
                     // if let EnemyKind::Laserie = enemy.kind {
                     //     let rot = target.rotation.to_euler(EulerRot::XYZ);
                     //     target.rotation = Quat::from_euler(EulerRot::XYZ, 0.0, rot.1, 0.0);
                     // }
-                    enemy_transform.rotation = enemy_transform
-                        .rotation
-                        .lerp(target.rotation, enemy.rotate_lerp);
+                    enemy_transform.rotation = enemy_transform.rotation.lerp(
+                        target.rotation,
+                        (enemy.rotate_lerp * difficulty.turn_scale()).min(1.0),
+                    );
                 } else {
                     enemy.within_range_of_player = false;
                 }
@@ -564,50 +745,22 @@ fn enemies_look_at(
     }
 }
 
-fn clean_up_dead(mut commands: Commands, time: Res<Time>, deads: Query<(Entity, &Dead)>) {
+fn clean_up_dead(mut commands: Commands, clock: Res<GameClock>, deads: Query<(Entity, &Dead)>) {
     for (entity, dead) in deads.iter() {
-        if time.seconds_since_startup() as f32 >= dead.time_to_despawn {
+        if clock.seconds() >= dead.died_at + CORPSE_LIFETIME {
             commands.entity(entity).despawn_recursive();
         }
     }
 }
 
-// TODO move elsewhere
-#[derive(Component)]
-pub struct Explosion {
-    pub progress: f32,
-    pub speed: f32,
-    pub scale: f32,
-    pub handle: Handle<OrbMaterial>,
-}
-
-fn progress_explosions(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut explosions: Query<(Entity, &mut Transform, &mut Explosion)>,
-    mut orb_materials: ResMut<Assets<OrbMaterial>>,
-) {
-    for (entity, mut trans, mut explosion) in explosions.iter_mut() {
-        explosion.progress += time.delta_seconds() * explosion.speed;
-        if explosion.progress >= 1.0 {
-            commands.entity(entity).despawn();
-        } else {
-            trans.scale *= 1.0 + explosion.progress * explosion.scale;
-            if let Some(mat) = orb_materials.get_mut(explosion.handle.clone()) {
-                mat.material_properties.alpha = 1.0 - explosion.progress;
-            }
-        }
-    }
-}
-
 fn player_takes_damage(
-    time: Res<Time>,
+    clock: Res<GameClock>,
     mut player_events: EventReader<PlayerEvent>,
     mut enemies_state: ResMut<EnemiesState>,
 ) {
     for player_event in player_events.iter() {
-        if let PlayerEvent::Hit { laser: _laser } = player_event {
-            enemies_state.last_time_player_took_damage = time.seconds_since_startup() as f32;
+        if let PlayerEvent::Hit { .. } = player_event {
+            enemies_state.last_time_player_took_damage = clock.seconds();
         }
     }
 }
@@ -615,100 +768,25 @@ fn player_takes_damage(
 fn handle_scoreboard_event(
     mut commands: Commands,
     mut events: EventReader<ScoreboardEvent>,
-    model_assets: Res<ModelAssets>,
     waypoints: Res<Waypoints>,
     enemies_state: Res<EnemiesState>,
     mut timer: ResMut<EnemySpawnTimer>,
-    mut polylines: ResMut<Assets<Polyline>>,
-    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
 ) {
     for event in events.iter() {
-        if let ScoreboardEvent::LevelUp = event {
-            let spawn_point = waypoints.outfront.choose(&mut rand::thread_rng()).unwrap();
-            if enemies_state.current_level % 2 == 1 {
-                commands
-                    .spawn_bundle((
-                        Transform::from_xyz(spawn_point.x, spawn_point.y, spawn_point.z)
-                            .with_scale(Vec3::new(3.0, 3.0, 3.0)),
-                        GlobalTransform::default(),
-                    ))
-                    .insert(RigidBody::Dynamic)
-                    .insert(CollisionShape::Sphere { radius: 2.7 * 3.0 })
-                    .insert(CollisionLayers::from_bits(
-                        Layer::Enemy.to_bits(),
-                        Layer::all_bits(),
-                    ))
-                    .insert(PhysicMaterial {
-                        density: 2.0, // Value must be greater than 0.0
-                        ..Default::default()
-                    })
-                    .insert(EnemyLastFired(Timer::from_seconds(0.9, true)))
-                    .insert(Enemy {
-                        health: 6000,
-                        range: 200.0,
-                        update_destination_timer: Timer::from_seconds(2.0, true),
-                        move_speed: 15.0,
-                        weapon_damage: 60.0,
-                        weapon_splash_radius: 12.0,
-                        rotate_lerp: 0.1,
-                        big: true,
-                        ..Default::default()
-                    })
-                    .insert(OrbieEnemy)
-                    .insert(Alive)
-                    .with_children(|parent| {
-                        parent.spawn_scene(model_assets.unit2.clone());
-                    });
+        if *event == ScoreboardEvent::LevelUp && mode.bosses_on_level_up() {
+            let spawn_point = waypoints.outfront.choose(&mut rng.0).unwrap();
+            let kind = if enemies_state.current_level % 2 == 1 {
+                EnemyKind::BigOrbie
             } else {
-                commands
-                    .spawn_bundle((
-                        Transform::from_xyz(spawn_point.x, spawn_point.y, spawn_point.z)
-                            .with_scale(Vec3::new(3.0, 3.0, 3.0)),
-                        GlobalTransform::default(),
-                    ))
-                    .insert(RigidBody::Dynamic)
-                    .insert(CollisionShape::Sphere { radius: 2.7 })
-                    .insert(CollisionLayers::from_bits(
-                        Layer::Enemy.to_bits(),
-                        Layer::all_bits(),
-                    ))
-                    .insert(PhysicMaterial {
-                        density: 1.0, // Value must be greater than 0.0
-                        ..Default::default()
-                    })
-                    .insert(EnemyLastFired(Timer::from_seconds(0.9, true)))
-                    .insert(Enemy {
-                        health: 5000,
-                        range: 180.0,
-                        update_destination_timer: Timer::from_seconds(2.0, true),
-                        move_speed: 25.0,
-                        weapon_damage: 30.0,
-                        weapon_splash_radius: 0.0,
-                        rotate_lerp: 0.3,
-                        big: true,
-                        ..Default::default()
-                    })
-                    .insert(LaserieEnemy)
-                    .insert(Alive)
-                    .with_children(|parent| {
-                        parent.spawn_scene(model_assets.unit1.clone());
-                    })
-                    .with_children(|parent| {
-                        parent.spawn_bundle(PolylineBundle {
-                            polyline: polylines.add(Polyline {
-                                vertices: vec![Vec3::ZERO, Vec3::ZERO],
-                            }),
-                            material: polyline_materials.add(PolylineMaterial {
-                                width: 90.0,
-                                color: Color::rgba(1.0, 0.0, 1.0, 0.9),
-                                perspective: true,
-                            }),
-                            visibility: Visibility { is_visible: true },
-                            ..Default::default()
-                        });
-                    })
-                    .insert(HasLaser);
-            }
+                EnemyKind::BigLaserie
+            };
+            spawn_enemy(
+                &mut commands,
+                kind,
+                Transform::from_xyz(spawn_point.x, spawn_point.y, spawn_point.z),
+            );
             if enemies_state.current_level == 1 {
                 //spawn_enemies_on_timer will set the duration back to 1 when the timer triggers
                 timer.0.set_duration(Duration::from_secs(7));
@@ -0,0 +1,222 @@
+use bevy::prelude::*;
+use bevy_polyline::{Polyline, PolylineBundle, PolylineMaterial};
+
+use crate::{
+    assets::{
+        orb_material::{OrbMaterial, OrbProperties},
+        GameState, ModelAssets,
+    },
+    run::RunScoped,
+    ui::menu::GamePreferences,
+    world::LevelAsset,
+    GameClock,
+};
+
+use super::{bullet::Bullet, laserie::LaserBeam, orbie::OrbieEnemy, Dead, Enemy, EnemyEvent};
+
+/// Seconds a dead enemy stays in sight in potato mode
+const POTATO_CORPSE_TIME: f32 = 2.0;
+
+/// Models, lasers and explosions for the enemies the simulation spawns
+pub struct EnemyVisualsPlugin;
+
+impl Plugin for EnemyVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(add_enemy_models)
+                .with_system(add_bullet_models)
+                .with_system(add_lasers_to_laserie)
+                .with_system(draw_laser_beams)
+                .with_system(spawn_enemy_explosions)
+                .with_system(progress_explosions)
+                .with_system(clear_potato_corpses),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Explosion {
+    pub progress: f32,
+    pub speed: f32,
+    pub scale: f32,
+    pub handle: Handle<OrbMaterial>,
+}
+
+/// Models are added separately so enemies can be spawned without any assets loaded
+fn add_enemy_models(
+    mut commands: Commands,
+    model_assets: Res<ModelAssets>,
+    enemies: Query<(Entity, Option<&OrbieEnemy>), Added<Enemy>>,
+) {
+    for (entity, orbie) in enemies.iter() {
+        let scene = if orbie.is_some() {
+            model_assets.unit2.clone()
+        } else {
+            model_assets.unit1.clone()
+        };
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn_scene(scene);
+        });
+    }
+}
+
+/// Potato mode takes the models off dead enemies early, their bodies stay in the simulation until
+/// it despawns them
+fn clear_potato_corpses(
+    mut commands: Commands,
+    clock: Res<GameClock>,
+    preferences: Res<GamePreferences>,
+    deads: Query<(&Dead, &Children)>,
+) {
+    if !preferences.potato {
+        return;
+    }
+    for (dead, children) in deads.iter() {
+        if clock.seconds() >= dead.died_at + POTATO_CORPSE_TIME {
+            for child in children.iter() {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn add_bullet_models(
+    mut commands: Commands,
+    mut orb_materials: ResMut<Assets<OrbMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    bullets: Query<Entity, Added<Bullet>>,
+) {
+    for entity in bullets.iter() {
+        let orb_material_props = OrbProperties {
+            color_tint: Vec3::new(0.5, 0.5, 1.0),
+            radius: 0.0,
+            inner_radius: 0.28,
+            alpha: 1.0,
+            ..Default::default()
+        };
+        let orb_material = orb_materials.add(OrbMaterial {
+            material_properties: orb_material_props,
+            noise_texture: None,
+        });
+        let mesh = meshes.add(Mesh::from(shape::Icosphere {
+            radius: 2.0,
+            subdivisions: 1,
+        })); //TODO use billboard
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn()
+                .insert_bundle(MaterialMeshBundle {
+                    mesh,
+                    transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                    material: orb_material.clone(),
+                    ..Default::default()
+                })
+                .insert(LevelAsset::OrbMaterial {
+                    properties: orb_material_props,
+                    handle: orb_material,
+                });
+        });
+    }
+}
+
+fn add_lasers_to_laserie(
+    mut commands: Commands,
+    enemies: Query<(Entity, &Enemy), Added<LaserBeam>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    for (entity, enemy) in enemies.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn_bundle(PolylineBundle {
+                polyline: polylines.add(Polyline {
+                    vertices: vec![Vec3::ZERO, Vec3::ZERO],
+                }),
+                material: polyline_materials.add(PolylineMaterial {
+                    width: if enemy.big { 90.0 } else { 20.0 },
+                    color: Color::rgba(1.0, 0.0, 1.0, 0.9),
+                    perspective: true,
+                }),
+                visibility: Visibility { is_visible: true },
+                ..Default::default()
+            });
+        });
+    }
+}
+
+/// Points each Laserie's polyline at the end of its beam
+fn draw_laser_beams(
+    enemies: Query<(&LaserBeam, &Children), Changed<LaserBeam>>,
+    spawned_polys: Query<&Handle<Polyline>>,
+    mut polylines: ResMut<Assets<Polyline>>,
+) {
+    for (beam, children) in enemies.iter() {
+        for &child in children.iter() {
+            if let Ok(polyline_h) = spawned_polys.get(child) {
+                if let Some(polyline) = polylines.get_mut(polyline_h) {
+                    polyline.vertices[0] = Vec3::ZERO;
+                    polyline.vertices[1] = beam.0;
+                }
+            }
+        }
+    }
+}
+
+fn spawn_enemy_explosions(
+    mut commands: Commands,
+    mut enemy_events: EventReader<EnemyEvent>,
+    mut orb_materials: ResMut<Assets<OrbMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for enemy_event in enemy_events.iter() {
+        if let EnemyEvent::Killed { transform, .. } = enemy_event {
+            let orb_material_props = OrbProperties {
+                color_tint: Vec3::new(1.0, 0.95, 0.8),
+                alpha: 1.0,
+                ..Default::default()
+            };
+            let orb_material = orb_materials.add(OrbMaterial {
+                material_properties: orb_material_props,
+                noise_texture: None,
+            });
+            let mesh = meshes.add(Mesh::from(shape::Icosphere {
+                radius: 2.5,
+                subdivisions: 1,
+            })); //TODO use billboard
+            commands
+                .spawn()
+                .insert_bundle(MaterialMeshBundle {
+                    mesh,
+                    transform: *transform,
+                    material: orb_material.clone(),
+                    ..Default::default()
+                })
+                .insert(RunScoped)
+                .insert(Explosion {
+                    progress: 0.0,
+                    speed: 3.2,
+                    scale: 0.08,
+                    handle: orb_material,
+                });
+        }
+    }
+}
+
+fn progress_explosions(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut explosions: Query<(Entity, &mut Transform, &mut Explosion)>,
+    mut orb_materials: ResMut<Assets<OrbMaterial>>,
+) {
+    for (entity, mut trans, mut explosion) in explosions.iter_mut() {
+        explosion.progress += time.delta_seconds() * explosion.speed;
+        if explosion.progress >= 1.0 {
+            commands.entity(entity).despawn();
+        } else {
+            trans.scale *= 1.0 + explosion.progress * explosion.scale;
+            if let Some(mat) = orb_materials.get_mut(explosion.handle.clone()) {
+                mat.material_properties.alpha = 1.0 - explosion.progress;
+            }
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt;
+
+use bevy::{
+    input::mouse::MouseMotion,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_egui::egui::{Color32, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::player::MovementSettings;
+
+use super::{gamepad::shape_stick, ActiveDevice};
+
+/// Named things the player can do, gameplay systems query these instead of raw input
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Sprint,
+    Jump,
+    Crouch,
+    Dash,
+    Fire,
+    AltFire,
+    Zoom,
+    Ultimate,
+    WeaponNext,
+    Noclip,
+    Menu,
+}
+
+impl Action {
+    pub fn all() -> [Action; 15] {
+        [
+            Action::MoveForward,
+            Action::MoveBack,
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::Sprint,
+            Action::Jump,
+            Action::Crouch,
+            Action::Dash,
+            Action::Fire,
+            Action::AltFire,
+            Action::Zoom,
+            Action::Ultimate,
+            Action::WeaponNext,
+            Action::Noclip,
+            Action::Menu,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "Forward",
+            Action::MoveBack => "Back",
+            Action::MoveLeft => "Left",
+            Action::MoveRight => "Right",
+            Action::Sprint => "Sprint",
+            Action::Jump => "Jump",
+            Action::Crouch => "Crouch",
+            Action::Dash => "Dash",
+            Action::Fire => "Fire",
+            Action::AltFire => "Alt Fire",
+            Action::Zoom => "Zoom",
+            Action::Ultimate => "Ultimate",
+            Action::WeaponNext => "Next Weapon",
+            Action::Noclip => "Noclip Camera",
+            Action::Menu => "Menu",
+        }
+    }
+
+    /// Actions that still work while the menu has the cursor
+    fn works_in_menu(&self) -> bool {
+        matches!(self, Action::Menu)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl InputBinding {
+    fn is_gamepad(&self) -> bool {
+        matches!(self, InputBinding::Gamepad(_))
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputBinding::Key(key) => write!(f, "{:?}", key),
+            InputBinding::Mouse(button) => write!(f, "Mouse {:?}", button),
+            InputBinding::Gamepad(button) => write!(f, "Pad {:?}", button),
+        }
+    }
+}
+
+/// Binds every [`Action`] to one or more inputs
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<InputBinding>>,
+    capturing: Option<Action>,
+    last_conflict: Option<(InputBinding, Action)>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        use InputBinding::{Gamepad, Key, Mouse};
+
+        let mut map = ActionMap {
+            bindings: HashMap::default(),
+            capturing: None,
+            last_conflict: None,
+        };
+        for (action, bindings) in [
+            (Action::MoveForward, vec![Key(KeyCode::W)]),
+            (Action::MoveBack, vec![Key(KeyCode::S)]),
+            (Action::MoveLeft, vec![Key(KeyCode::A)]),
+            (Action::MoveRight, vec![Key(KeyCode::D)]),
+            (
+                Action::Sprint,
+                vec![Key(KeyCode::LShift), Gamepad(GamepadButtonType::LeftThumb)],
+            ),
+            (
+                Action::Jump,
+                vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::South)],
+            ),
+            (
+                Action::Crouch,
+                vec![Key(KeyCode::LControl), Gamepad(GamepadButtonType::East)],
+            ),
+            (
+                Action::Dash,
+                vec![Key(KeyCode::E), Gamepad(GamepadButtonType::LeftTrigger)],
+            ),
+            (
+                Action::Fire,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButtonType::RightTrigger2),
+                ],
+            ),
+            (
+                Action::AltFire,
+                vec![
+                    Mouse(MouseButton::Right),
+                    Gamepad(GamepadButtonType::LeftTrigger2),
+                ],
+            ),
+            (
+                Action::Zoom,
+                vec![
+                    Mouse(MouseButton::Middle),
+                    Key(KeyCode::Z),
+                    Gamepad(GamepadButtonType::RightThumb),
+                ],
+            ),
+            (
+                Action::Ultimate,
+                vec![Key(KeyCode::F), Gamepad(GamepadButtonType::RightTrigger)],
+            ),
+            (
+                Action::WeaponNext,
+                vec![Key(KeyCode::Q), Gamepad(GamepadButtonType::North)],
+            ),
+            (Action::Noclip, vec![Key(KeyCode::F1)]),
+            (
+                Action::Menu,
+                vec![
+                    Key(KeyCode::Escape),
+                    Key(KeyCode::Tab),
+                    Gamepad(GamepadButtonType::Start),
+                ],
+            ),
+        ] {
+            for binding in bindings {
+                if let Err(other) = map.bind(action, binding) {
+                    panic!(
+                        "default binding {} is used by {:?} and {:?}",
+                        binding, action, other
+                    );
+                }
+            }
+        }
+        map
+    }
+}
+
+impl ActionMap {
+    pub fn bindings(&self, action: Action) -> &[InputBinding] {
+        self.bindings.get(&action).map_or(&[], |b| b.as_slice())
+    }
+
+    /// Returns the action other than `action` that already uses `binding`
+    pub fn conflict(&self, action: Action, binding: InputBinding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(other, bindings)| **other != action && bindings.contains(&binding))
+            .map(|(other, _)| *other)
+    }
+
+    /// Every binding that is used by more than one action
+    pub fn conflicts(&self) -> Vec<(InputBinding, Action, Action)> {
+        let mut conflicts = Vec::new();
+        for action in Action::all() {
+            for binding in self.bindings(action) {
+                if let Some(other) = self.conflict(action, *binding) {
+                    if (action as usize) < (other as usize) {
+                        conflicts.push((*binding, action, other));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Adds `binding` to `action`, refusing it if another action already uses it
+    pub fn bind(&mut self, action: Action, binding: InputBinding) -> Result<(), Action> {
+        if let Some(other) = self.conflict(action, binding) {
+            return Err(other);
+        }
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        Ok(())
+    }
+
+    /// Removes every binding from `action`
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    /// Replaces the bindings of `action` that come from the same kind of device as `binding`
+    pub fn rebind(&mut self, action: Action, binding: InputBinding) -> Result<(), Action> {
+        if let Some(other) = self.conflict(action, binding) {
+            return Err(other);
+        }
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|b| b.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+        Ok(())
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing.is_some()
+    }
+
+    pub fn build_ui(&mut self, ui: &mut Ui) {
+        for action in Action::all() {
+            ui.horizontal(|ui| {
+                if ui.button("modify").clicked() {
+                    self.capturing = Some(action);
+                    self.last_conflict = None;
+                }
+                let bindings = self
+                    .bindings(action)
+                    .iter()
+                    .map(|binding| binding.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if self.capturing == Some(action) {
+                    ui.label(&format!("press an input: {}", action.label()));
+                } else {
+                    ui.label(&format!("{}: {}", bindings, action.label()));
+                }
+            });
+        }
+        if let Some((binding, other)) = self.last_conflict {
+            ui.colored_label(
+                Color32::RED,
+                format!("{} is already bound to {}", binding, other.label()),
+            );
+        }
+        for (binding, a, b) in self.conflicts() {
+            ui.colored_label(
+                Color32::RED,
+                format!(
+                    "{} is bound to both {} and {}",
+                    binding,
+                    a.label(),
+                    b.label()
+                ),
+            );
+        }
+    }
+
+    fn capture(&mut self, binding: InputBinding) {
+        if let Some(action) = self.capturing.take() {
+            self.last_conflict = self
+                .rebind(action, binding)
+                .err()
+                .map(|other| (binding, other));
+        }
+    }
+}
+
+/// The actions that are active this frame
+#[derive(Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    previous: HashSet<Action>,
+    /// Movement input, X right and Y forward, no longer than 1
+    pub move_axis: Vec2,
+    /// Yaw and pitch change this frame in radians
+    pub look_delta: Vec2,
+}
+
+impl ActionState {
+    /// Starts a new frame, remembering what was pressed for `just_pressed`
+    pub fn begin_frame(&mut self) {
+        self.previous = std::mem::take(&mut self.pressed);
+        self.move_axis = Vec2::ZERO;
+        self.look_delta = Vec2::ZERO;
+    }
+
+    /// Releases everything except `keep`, for when something else takes over gameplay input
+    pub fn release_all_except(&mut self, keep: &[Action]) {
+        self.pressed.retain(|action| keep.contains(action));
+        self.move_axis = Vec2::ZERO;
+        self.look_delta = Vec2::ZERO;
+    }
+
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action) && !self.previous.contains(&action)
+    }
+}
+
+/// Records the next input pressed while the preferences menu is rebinding an action
+pub fn capture_rebind(
+    mut action_map: ResMut<ActionMap>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    if !action_map.is_capturing() {
+        return;
+    }
+    if let Some(key) = keys.get_just_pressed().next() {
+        action_map.capture(InputBinding::Key(*key));
+    } else if let Some(button) = mouse_buttons.get_just_pressed().next() {
+        action_map.capture(InputBinding::Mouse(*button));
+    } else if let Some(GamepadButton(_, button_type)) = gamepad_buttons.get_just_pressed().next() {
+        action_map.capture(InputBinding::Gamepad(*button_type));
+    }
+}
+
+/// Turns raw device input into the [`ActionState`] for this frame
+pub fn update_action_state(
+    mut action_state: ResMut<ActionState>,
+    action_map: Res<ActionMap>,
+    settings: Res<MovementSettings>,
+    active_device: Res<ActiveDevice>,
+    time: Res<Time>,
+    windows: Res<Windows>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut smoothed_mouse: Local<Vec2>,
+) {
+    if action_map.is_capturing() {
+        return;
+    }
+    let in_game = windows.get_primary().map_or(false, |window| {
+        window.is_focused() && window.cursor_locked()
+    });
+
+    for action in Action::all() {
+        if !in_game && !action.works_in_menu() {
+            continue;
+        }
+        let pressed = action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                InputBinding::Key(key) => keys.pressed(*key),
+                InputBinding::Mouse(button) => mouse_buttons.pressed(*button),
+                InputBinding::Gamepad(button_type) => {
+                    active_device.pressed(&gamepad_buttons, *button_type)
+                }
+            });
+        if pressed {
+            action_state.press(action);
+        }
+    }
+
+    if !in_game {
+        // Still drain the events so they don't pile up into a jump when the game resumes
+        mouse_motion.iter().for_each(drop);
+        *smoothed_mouse = Vec2::ZERO;
+        return;
+    }
+
+    let mut move_axis = Vec2::ZERO;
+    if action_state.pressed(Action::MoveForward) {
+        move_axis.y += 1.0;
+    }
+    if action_state.pressed(Action::MoveBack) {
+        move_axis.y -= 1.0;
+    }
+    if action_state.pressed(Action::MoveRight) {
+        move_axis.x += 1.0;
+    }
+    if action_state.pressed(Action::MoveLeft) {
+        move_axis.x -= 1.0;
+    }
+    let stick = shape_stick(
+        active_device.stick(
+            &gamepad_axes,
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+        ),
+        settings.gamepad_deadzone,
+        1.0,
+    );
+    action_state.move_axis = (move_axis.normalize_or_zero() + stick).clamp_length_max(1.0);
+
+    let mut mouse_delta = Vec2::ZERO;
+    for ev in mouse_motion.iter() {
+        mouse_delta += ev.delta;
+    }
+    let dt = time.delta_seconds().max(0.0001);
+    // Acceleration grows with mouse speed in pixels per millisecond
+    mouse_delta *= 1.0 + settings.mouse_acceleration * mouse_delta.length() / (dt * 1000.0);
+    // Same smoothing feel at any frame rate
+    let smoothing = settings.mouse_smoothing.powf(dt * 60.0);
+    *smoothed_mouse = smoothed_mouse.lerp(mouse_delta, 1.0 - smoothing);
+    let sensitivity = Vec2::new(
+        settings.horizontal_sensitivity,
+        settings.vertical_sensitivity,
+    );
+    let mut look_delta = -(sensitivity * 0.01 * *smoothed_mouse) * (std::f32::consts::PI / 180.0);
+    let stick = shape_stick(
+        active_device.stick(
+            &gamepad_axes,
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+        ),
+        settings.gamepad_deadzone,
+        settings.gamepad_response_curve,
+    );
+    // At full deflection a sensitivity of 1.0 turns 60 degrees per second
+    let gamepad_rate = (settings.gamepad_sensitivity * 60.0 * time.delta_seconds()).to_radians();
+    look_delta += Vec2::new(-stick.x, stick.y) * gamepad_rate;
+    if settings.invert_y {
+        look_delta.y = -look_delta.y;
+    }
+    action_state.look_delta = look_delta;
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+pub const GAMEPAD_MENU_ACCEPT: GamepadButtonType = GamepadButtonType::South;
+
+/// The device the player used most recently
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActiveDevice {
+    KeyboardMouse,
+    Gamepad(Gamepad),
+}
+
+impl Default for ActiveDevice {
+    fn default() -> Self {
+        ActiveDevice::KeyboardMouse
+    }
+}
+
+impl ActiveDevice {
+    pub fn gamepad(&self) -> Option<Gamepad> {
+        match self {
+            ActiveDevice::Gamepad(gamepad) => Some(*gamepad),
+            ActiveDevice::KeyboardMouse => None,
+        }
+    }
+
+    pub fn pressed(&self, buttons: &Input<GamepadButton>, button_type: GamepadButtonType) -> bool {
+        self.gamepad().map_or(false, |gamepad| {
+            buttons.pressed(GamepadButton(gamepad, button_type))
+        })
+    }
+
+    pub fn just_pressed(
+        &self,
+        buttons: &Input<GamepadButton>,
+        button_type: GamepadButtonType,
+    ) -> bool {
+        self.gamepad().map_or(false, |gamepad| {
+            buttons.just_pressed(GamepadButton(gamepad, button_type))
+        })
+    }
+
+    /// Raw stick position, Y up
+    pub fn stick(&self, axes: &Axis<GamepadAxis>, x: GamepadAxisType, y: GamepadAxisType) -> Vec2 {
+        self.gamepad().map_or(Vec2::ZERO, |gamepad| {
+            Vec2::new(
+                axes.get(GamepadAxis(gamepad, x)).unwrap_or(0.0),
+                axes.get(GamepadAxis(gamepad, y)).unwrap_or(0.0),
+            )
+        })
+    }
+}
+
+/// Applies a radial deadzone, then rescales the remaining range and shapes it with
+/// `response_curve` as an exponent (1.0 is linear, higher values give finer aim near the center)
+pub fn shape_stick(stick: Vec2, deadzone: f32, response_curve: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    stick / length * scaled.powf(response_curve)
+}
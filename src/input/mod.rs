@@ -0,0 +1,93 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, InputSystem},
+    prelude::*,
+};
+
+use crate::run::replay::play_back_input;
+
+use self::actions::{capture_rebind, update_action_state};
+
+pub mod actions;
+pub mod gamepad;
+
+pub use self::actions::{Action, ActionMap, ActionState};
+pub use self::gamepad::ActiveDevice;
+
+/// The [`ActionState`] gameplay reads, filled every frame by the devices, the bot or a replay
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>().add_system_to_stage(
+            CoreStage::PreUpdate,
+            begin_action_frame.before(ActionInputSystem),
+        );
+    }
+}
+
+/// Reads the keyboard, mouse and gamepads into the [`ActionState`]
+pub struct GameInputPlugin;
+
+/// Label for the system that fills [`ActionState`] from the input devices
+#[derive(Debug, PartialEq, Eq, Clone, Hash, SystemLabel)]
+pub struct ActionInputSystem;
+
+impl Plugin for GameInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveDevice>()
+            .init_resource::<ActionMap>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                detect_active_device.after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                capture_rebind
+                    .after(InputSystem)
+                    .before(update_action_state),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_action_state
+                    .label(ActionInputSystem)
+                    .after(detect_active_device)
+                    .before(play_back_input),
+            );
+    }
+}
+
+/// Releases last frame's actions, remembering them for `just_pressed`
+fn begin_action_frame(mut action_state: ResMut<ActionState>) {
+    action_state.begin_frame();
+}
+
+/// Switches the active device to whatever was used last
+fn detect_active_device(
+    mut active_device: ResMut<ActiveDevice>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+) {
+    let mut device = *active_device;
+    if keyboard_events.iter().next().is_some() || mouse_button_events.iter().next().is_some() {
+        device = ActiveDevice::KeyboardMouse;
+    }
+    for GamepadEvent(gamepad, event_type) in gamepad_events.iter() {
+        match event_type {
+            GamepadEventType::ButtonChanged(_, value) if *value > 0.5 => {
+                device = ActiveDevice::Gamepad(*gamepad);
+            }
+            GamepadEventType::AxisChanged(_, value) if value.abs() > 0.5 => {
+                device = ActiveDevice::Gamepad(*gamepad);
+            }
+            GamepadEventType::Disconnected if device == ActiveDevice::Gamepad(*gamepad) => {
+                device = ActiveDevice::KeyboardMouse;
+            }
+            _ => {}
+        }
+    }
+    if device != *active_device {
+        info!("Active input device: {:?}", device);
+        *active_device = device;
+    }
+}
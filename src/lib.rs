@@ -1,49 +1,202 @@
-use assets::AssetsPlugin;
-use audio::GameAudioPlugin;
-use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*};
-use bevy_kira_audio::AudioPlugin;
+use std::time::Duration;
+
+use assets::{AssetsPlugin, GameState};
+use bevy::{
+    app::{PluginGroupBuilder, ScheduleRunnerPlugin},
+    core::CorePlugin,
+    diagnostic::FrameTimeDiagnosticsPlugin,
+    hierarchy::HierarchyPlugin,
+    prelude::*,
+    transform::TransformPlugin,
+};
 use bevy_polyline::PolylinePlugin;
 use bevy_tweening::TweeningPlugin;
+use bot::BotPlugin;
+use config::ConfigPlugin;
 //use console::ConsolePlugin;
-use enemies::EnemiesPlugin;
-use heron::{Gravity, PhysicsLayer, PhysicsPlugin};
-use player::PlayerPlugin;
-use ui::UiPlugin;
-use world::WorldPlugin;
+use enemies::{visuals::EnemyVisualsPlugin, EnemiesPlugin};
+use heron::{Gravity, PhysicsLayer, PhysicsPlugin, PhysicsSteps, PhysicsTime};
+use input::{ActionsPlugin, GameInputPlugin};
+use modes::ModesPlugin;
+use player::{visuals::PlayerVisualsPlugin, PlayerPlugin};
+use run::{launch::LaunchPlugin, replay::ReplayPlugin, snapshot::SnapshotPlugin, RunPlugin};
+use ui::{scoreboard::ScoreboardPlugin, UiPlugin};
+use world::{level1::LevelOnePlugin, WorldPlugin};
 
 mod assets;
 mod audio;
+mod bot;
+mod config;
 //mod console;
 mod enemies;
+mod input;
+mod modes;
 mod player;
+pub mod run;
 mod ui;
 mod world;
 
+pub use assets::GameState;
+pub use audio::{GameAudioPlugin, SilentAudioPlugin};
+pub use bot::BotSettings;
+pub use enemies::{
+    bullet::BulletBundle, spawn_enemy_in_world, EnemiesState, Enemy, EnemyKind, EnemySpawnTimer,
+    Waypoints,
+};
+pub use input::{Action, ActionInputSystem, ActionState};
+pub use modes::{difficulty::Difficulty, GameMode, ModeState};
+pub use player::{InputState, Player, WeaponKind};
+pub use ui::scoreboard::{Scoreboard, ScoreboardEvent};
+pub use world::level_collision_layers;
+
+/// Longest step gameplay takes in one frame, a hitch slows the game down rather than letting
+/// things tunnel through walls
+const MAX_STEP: Duration = Duration::from_millis(50);
+/// Physics can't step by nothing
+const MIN_STEP: Duration = Duration::from_micros(100);
+
+/// The whole game, the simulation with everything that draws it and plays its sound on top
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app
+        app.add_plugins(SimulationPlugins)
+            .add_plugins(PresentationPlugins);
+    }
+}
+
+/// Gameplay on its own: physics, the player, enemies, game modes and scoring
+///
+/// Runs without a window, GPU or audio device, see [`HeadlessPlugins`].
+pub struct SimulationPlugins;
+
+impl PluginGroup for SimulationPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(GameCorePlugin)
+            .add(ActionsPlugin)
+            .add(RunPlugin)
+            .add(ModesPlugin)
+            .add(PlayerPlugin)
+            .add(EnemiesPlugin)
+            .add(ScoreboardPlugin)
+            .add(WorldPlugin)
+            .add(BotPlugin)
+            .add(ReplayPlugin)
+            .add(SnapshotPlugin)
+            .add(LaunchPlugin);
+    }
+}
+
+/// Everything that draws the simulation, plays its sound or reads input devices
+pub struct PresentationPlugins;
+
+impl PluginGroup for PresentationPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
             // External plugins
-            .add_plugin(AudioPlugin)
-            .add_plugin(FrameTimeDiagnosticsPlugin::default())
-            .add_plugin(PolylinePlugin)
-            .add_plugin(PhysicsPlugin::default())
-            .insert_resource(Gravity::from(Vec3::new(0.0, -9.81, 0.0)))
-            .add_plugin(TweeningPlugin)
+            .add(FrameTimeDiagnosticsPlugin::default())
+            .add(PolylinePlugin)
+            .add(TweeningPlugin)
             // Game plugins
-            .add_plugin(AssetsPlugin)
-            //.add_plugin(ConsolePlugin) // Need 0.7 compatible version
-            .add_plugin(EnemiesPlugin)
-            .add_plugin(GameAudioPlugin)
-            .add_plugin(PlayerPlugin)
-            .add_plugin(UiPlugin)
-            .add_plugin(WorldPlugin)
-            // Main systems
-            .add_startup_system(mouse_startup);
+            .add(AssetsPlugin)
+            //.add(ConsolePlugin) // Need 0.7 compatible version
+            .add(ConfigPlugin)
+            .add(GameAudioPlugin)
+            .add(GameInputPlugin)
+            .add(LevelOnePlugin)
+            .add(EnemyVisualsPlugin)
+            .add(PlayerVisualsPlugin)
+            .add(UiPlugin);
+    }
+}
+
+/// [`MinimalPlugins`] plus the transform hierarchy the simulation needs, to run
+/// [`SimulationPlugins`] without a window
+pub struct HeadlessPlugins;
+
+impl PluginGroup for HeadlessPlugins {
+    fn build(&mut self, group: &mut PluginGroupBuilder) {
+        group
+            .add(CorePlugin::default())
+            .add(ScheduleRunnerPlugin::default())
+            .add(TransformPlugin::default())
+            .add(HierarchyPlugin::default());
+    }
+}
+
+/// Physics, the game clock and the game state, which the rest of the simulation builds on
+pub struct GameCorePlugin;
+
+impl Plugin for GameCorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(PhysicsPlugin::default())
+            .insert_resource(Gravity::from(Vec3::new(0.0, -9.81, 0.0)))
+            .add_state(GameState::Menu)
+            .init_resource::<GameClock>()
+            .add_system_to_stage(CoreStage::First, step_game_clock.label(GameClockSystem))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(tick_game_clock.before(GameplaySystem::Player)),
+            )
+            // Freeze physics while another state is pushed on top of Playing
+            .add_system_set(SystemSet::on_pause(GameState::Playing).with_system(pause_physics))
+            .add_system_set(SystemSet::on_resume(GameState::Playing).with_system(resume_physics));
     }
 }
 
+/// Seconds of gameplay so far, stands still while another state is pushed on top of Playing
+///
+/// Gameplay systems step by [`GameClock::delta`] rather than [`Time`] so a replay can run them
+/// with the steps that were recorded.
+#[derive(Default)]
+pub struct GameClock {
+    elapsed: f32,
+    delta: Duration,
+    /// Steps every frame by this instead of the time the frame took, for tests
+    pub fixed_step: Option<Duration>,
+}
+
+impl GameClock {
+    pub fn seconds(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Step of the current frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn tick(&mut self, seconds: f32) {
+        self.elapsed += seconds;
+    }
+}
+
+/// Label for the system that picks the step gameplay and physics take this frame
+#[derive(Debug, PartialEq, Eq, Clone, Hash, SystemLabel)]
+pub struct GameClockSystem;
+
+/// Phases of a gameplay frame, each running after the one before. Systems in one phase that
+/// share data are ordered among themselves, so a frame always runs the same way and a replay plays
+/// out like its recording.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, SystemLabel)]
+pub enum GameplaySystem {
+    /// Throws away the last run on [`run::ResetRun`]
+    Reset,
+    /// Movement, look and weapons
+    Player,
+    /// Enemies moving, firing, dying and spawning, and their bullets landing
+    Enemies,
+    /// Waves, bosses and the end of the run
+    Modes,
+    /// Tallies what happened this frame
+    Scoreboard,
+}
+
 #[derive(PhysicsLayer)]
 enum Layer {
     Bullet,
@@ -65,8 +218,28 @@ impl Layer {
     }
 }
 
-fn mouse_startup(mut windows: ResMut<Windows>) {
-    let window = windows.get_primary_mut().unwrap();
-    window.set_cursor_lock_mode(false);
-    window.set_cursor_visibility(true);
+fn step_game_clock(
+    time: Res<Time>,
+    mut clock: ResMut<GameClock>,
+    mut physics_steps: ResMut<PhysicsSteps>,
+) {
+    let step = clock.fixed_step.unwrap_or_else(|| {
+        // Whole microseconds, so a replay can record them exactly
+        Duration::from_micros(time.delta().as_micros() as u64).clamp(MIN_STEP, MAX_STEP)
+    });
+    clock.delta = step;
+    *physics_steps = PhysicsSteps::every_frame(step);
+}
+
+fn tick_game_clock(mut clock: ResMut<GameClock>) {
+    let delta = clock.delta_seconds();
+    clock.tick(delta);
+}
+
+fn pause_physics(mut physics_time: ResMut<PhysicsTime>) {
+    physics_time.pause();
+}
+
+fn resume_physics(mut physics_time: ResMut<PhysicsTime>) {
+    physics_time.resume();
 }
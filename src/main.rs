@@ -1,34 +1,94 @@
 use bevy::{
+    log::LogPlugin,
     prelude::*,
-    window::{PresentMode, WindowMode, WindowResizeConstraints},
+    window::{PresentMode, WindowResizeConstraints},
+};
+use game::{
+    run::{
+        launch::LaunchOptions,
+        replay::{Replay, ReplayPlayer},
+    },
+    BotSettings, GameAudioPlugin, HeadlessPlugins, PresentationPlugins, SilentAudioPlugin,
+    SimulationPlugins,
 };
-use game::GamePlugin;
 
 fn main() {
-    App::new()
-        .insert_resource(WindowDescriptor {
-            title: "Confluence of Futility".to_string(),
-            width: 1280.0,
-            height: 720.0,
-            position: None,
-            resize_constraints: WindowResizeConstraints {
-                min_width: 256.0,
-                min_height: 256.0,
-                ..Default::default()
-            },
-            scale_factor_override: None, //Some(1.0), //Needed for some mobile devices, but disables scaling
-            present_mode: PresentMode::Immediate,
-            resizable: true,
-            decorations: true,
-            cursor_locked: false,
-            cursor_visible: true,
-            mode: WindowMode::Windowed,
-            transparent: false,
-            #[cfg(target_arch = "wasm32")]
-            canvas: None,
-        })
-        .insert_resource(Msaa { samples: 4 })
-        .add_plugins(DefaultPlugins)
-        .add_plugin(GamePlugin)
-        .run();
+    // `--help` lists the launch options, which start a run without the menu, pick its settings,
+    // end the game with a stats dump, let the bot play or play a replay, see `LaunchOptions`
+    if std::env::args().any(|arg| arg == "--help") {
+        println!("{}", LaunchOptions::USAGE);
+        return;
+    }
+    let options = match LaunchOptions::from_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, LaunchOptions::USAGE);
+            std::process::exit(1);
+        }
+    };
+    let (width, height) = options.resolution.unwrap_or((1280.0, 720.0));
+    let window_mode = options.window_mode;
+    let audio = options.audio;
+    let mut app = App::new();
+    if let Some(runs) = options.autoplay {
+        app.insert_resource(BotSettings {
+            enabled: true,
+            runs,
+            ..Default::default()
+        });
+    }
+    if let Some(path) = &options.replay {
+        match Replay::load(path) {
+            Ok(replay) => {
+                app.insert_resource(ReplayPlayer::autoplay(replay, options.verify_replay));
+            }
+            Err(err) => {
+                eprintln!("Could not play {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if options.headless() {
+        app.insert_resource(options)
+            .add_plugins(HeadlessPlugins)
+            .add_plugin(LogPlugin)
+            .add_plugins(SimulationPlugins)
+            .run();
+        return;
+    }
+    app.insert_resource(WindowDescriptor {
+        title: "Confluence of Futility".to_string(),
+        width,
+        height,
+        position: None,
+        resize_constraints: WindowResizeConstraints {
+            min_width: 256.0,
+            min_height: 256.0,
+            ..Default::default()
+        },
+        scale_factor_override: None, //Some(1.0), //Needed for some mobile devices, but disables scaling
+        present_mode: PresentMode::Immediate,
+        resizable: true,
+        decorations: true,
+        cursor_locked: false,
+        cursor_visible: true,
+        mode: window_mode,
+        transparent: false,
+        #[cfg(target_arch = "wasm32")]
+        canvas: None,
+    })
+    .insert_resource(Msaa { samples: 4 })
+    .insert_resource(options)
+    .add_plugins(DefaultPlugins)
+    // The pieces of `GamePlugin`, so the sound can be left out
+    .add_plugins(SimulationPlugins)
+    .add_plugins_with(PresentationPlugins, |group| {
+        if !audio {
+            group
+                .disable::<GameAudioPlugin>()
+                .add_after::<GameAudioPlugin, _>(SilentAudioPlugin);
+        }
+        group
+    })
+    .run();
 }
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+/// Picked on the start menu alongside the [`super::GameMode`], Normal keeps the level tables as
+/// the game always had them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Story,
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+impl Difficulty {
+    pub fn all() -> [Difficulty; 4] {
+        [
+            Difficulty::Story,
+            Difficulty::Normal,
+            Difficulty::Hard,
+            Difficulty::Nightmare,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Story => "Story",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Nightmare => "Nightmare",
+        }
+    }
+
+    /// The difficulty after this one, for cycling through them on the menu
+    pub fn next(&self) -> Difficulty {
+        let all = Difficulty::all();
+        let index = all
+            .iter()
+            .position(|difficulty| difficulty == self)
+            .unwrap_or(0);
+        all[(index + 1) % all.len()]
+    }
+
+    /// Scales the damage multiplier of every level
+    pub fn damage_scale(&self) -> f32 {
+        match self {
+            Difficulty::Story => 0.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.4,
+            Difficulty::Nightmare => 2.0,
+        }
+    }
+
+    /// Scales how many enemies can be alive at once on every level
+    pub fn enemy_count_scale(&self) -> f32 {
+        match self {
+            Difficulty::Story => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
+            Difficulty::Nightmare => 1.5,
+        }
+    }
+
+    /// How far from the player enemies may aim, in meters, rerolled whenever they move on
+    pub fn aim_error(&self) -> f32 {
+        match self {
+            Difficulty::Story => 3.0,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.5,
+            Difficulty::Nightmare => 0.25,
+        }
+    }
+
+    /// Scales how quickly enemies turn toward the player
+    pub fn turn_scale(&self) -> f32 {
+        match self {
+            Difficulty::Story => 0.6,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.3,
+            Difficulty::Nightmare => 1.6,
+        }
+    }
+
+    pub fn bullet_speed_scale(&self) -> f32 {
+        match self {
+            Difficulty::Story => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.2,
+            Difficulty::Nightmare => 1.4,
+        }
+    }
+
+    /// Scales the player's health and shield
+    pub fn player_health_scale(&self) -> f32 {
+        match self {
+            Difficulty::Story => 1.5,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 0.8,
+            Difficulty::Nightmare => 0.6,
+        }
+    }
+}
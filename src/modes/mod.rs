@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::GameState,
+    enemies::{Alive, EnemiesState, Enemy, EnemyKind, EnemySpawnTimer},
+    player::Player,
+    run::RunAppExt,
+    ui::scoreboard::{Scoreboard, ScoreboardEvent},
+    GameClock, GameplaySystem,
+};
+
+use self::difficulty::Difficulty;
+
+pub mod difficulty;
+
+/// Length of a Time Attack run in seconds
+const TIME_ATTACK_LENGTH: f32 = 180.0;
+/// Seconds to catch a breath between waves
+const WAVE_BREAK: f32 = 10.0;
+/// Clearing this wave wins a Waves run
+const FINAL_WAVE: usize = 10;
+/// Points for clearing a wave, multiplied by the wave number
+const WAVE_BONUS: usize = 500;
+/// Bosses to beat in a Boss Rush, they come one at a time
+const BOSS_RUSH_BOSSES: usize = 8;
+/// Finishing a Boss Rush faster than this many seconds earns 10 points for every second saved
+const BOSS_RUSH_PAR: f32 = 480.0;
+
+/// Spawn rules, win and lose conditions and scoring for each [`GameMode`]
+pub struct ModesPlugin;
+
+impl Plugin for ModesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<Difficulty>()
+            .init_run_resource::<ModeState>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .label(GameplaySystem::Modes)
+                    .after(GameplaySystem::Enemies)
+                    .with_system(count_bosses_defeated)
+                    .with_system(update_mode.after(count_bosses_defeated))
+                    .with_system(detect_game_over.after(update_mode)),
+            );
+    }
+}
+
+/// Picked on the start menu, stays the same across restarts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Endless climb through the levels, the original mode
+    Survival,
+    /// Survival against the clock
+    TimeAttack,
+    /// Fixed waves of enemies with breaks in between
+    Waves,
+    /// Bosses only, one after another
+    BossRush,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode::Survival
+    }
+}
+
+/// How a run ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Died,
+    TimeUp,
+    Won,
+}
+
+/// Progress through the current mode
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModeState {
+    /// Wave being fought or, during a break, the one just cleared
+    pub wave: usize,
+    /// Enemies of the current wave that haven't spawned yet
+    pub wave_remaining: usize,
+    /// Seconds left before the next wave
+    pub wave_break: f32,
+    pub bosses_defeated: usize,
+    pub outcome: Option<RunOutcome>,
+}
+
+impl ModeState {
+    /// Call after an enemy picked by [`GameMode::next_spawn`] has been spawned
+    pub fn spawned(&mut self) {
+        self.wave_remaining = self.wave_remaining.saturating_sub(1);
+    }
+}
+
+impl GameMode {
+    pub fn all() -> [GameMode; 4] {
+        [
+            GameMode::Survival,
+            GameMode::TimeAttack,
+            GameMode::Waves,
+            GameMode::BossRush,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "Survival",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Waves => "Waves",
+            GameMode::BossRush => "Boss Rush",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "Hold out for as long as you can.",
+            GameMode::TimeAttack => "Score as much as you can in three minutes.",
+            GameMode::Waves => "Clear ten waves, with a short break after each.",
+            GameMode::BossRush => "Defeat eight bosses, the faster the better.",
+        }
+    }
+
+    /// The mode after this one, for cycling through them on the menu
+    pub fn next(&self) -> GameMode {
+        let modes = GameMode::all();
+        let index = modes.iter().position(|mode| mode == self).unwrap_or(0);
+        modes[(index + 1) % modes.len()]
+    }
+
+    /// What to spawn when the spawn timer fires, if anything
+    pub fn next_spawn(
+        &self,
+        state: &ModeState,
+        max_enemies: usize,
+        alive: usize,
+        rng: &mut ChaCha8Rng,
+    ) -> Option<EnemyKind> {
+        let regular = |rng: &mut ChaCha8Rng| {
+            if rng.gen_range(0..=1) == 0 {
+                EnemyKind::Laserie
+            } else {
+                EnemyKind::Orbie
+            }
+        };
+        match self {
+            GameMode::Survival | GameMode::TimeAttack => {
+                (alive < max_enemies).then(|| regular(rng))
+            }
+            GameMode::Waves => {
+                if state.wave_break > 0.0 || state.wave_remaining == 0 || alive >= max_enemies {
+                    return None;
+                }
+                // Every third wave ends with a boss
+                if state.wave_remaining == 1 && state.wave % 3 == 0 {
+                    Some(boss(state.wave / 3))
+                } else {
+                    Some(regular(rng))
+                }
+            }
+            GameMode::BossRush => (alive == 0 && state.bosses_defeated < BOSS_RUSH_BOSSES)
+                .then(|| boss(state.bosses_defeated)),
+        }
+    }
+
+    /// Whether a kill moves the enemies up a level, making them hit harder
+    pub fn levels_up(&self, enemies_state: &EnemiesState, kind: EnemyKind) -> bool {
+        match self {
+            // Too few kills in a boss rush to go by the kill counts
+            GameMode::BossRush => kind.is_big(),
+            _ => {
+                enemies_state.enemies_killed
+                    >= enemies_state.get_level_params().kills_to_level_up as u32
+            }
+        }
+    }
+
+    /// Whether a boss joins the fight on every level up
+    pub fn bosses_on_level_up(&self) -> bool {
+        matches!(self, GameMode::Survival | GameMode::TimeAttack)
+    }
+
+    /// Progress shown next to the score
+    pub fn status(&self, state: &ModeState, scoreboard: &Scoreboard) -> Option<String> {
+        match self {
+            GameMode::Survival => None,
+            GameMode::TimeAttack => {
+                let seconds = (TIME_ATTACK_LENGTH - scoreboard.time_survived)
+                    .max(0.0)
+                    .ceil() as u32;
+                Some(format!("Time left: {}:{:02}", seconds / 60, seconds % 60))
+            }
+            GameMode::Waves if state.wave_break > 0.0 => Some(format!(
+                "Wave {} in {}",
+                state.wave + 1,
+                state.wave_break.ceil() as u32
+            )),
+            GameMode::Waves => Some(format!("Wave: {}/{}", state.wave.max(1), FINAL_WAVE)),
+            GameMode::BossRush => Some(format!(
+                "Bosses: {}/{}",
+                state.bosses_defeated, BOSS_RUSH_BOSSES
+            )),
+        }
+    }
+}
+
+/// Bosses take turns between the two kinds
+fn boss(index: usize) -> EnemyKind {
+    if index % 2 == 0 {
+        EnemyKind::BigOrbie
+    } else {
+        EnemyKind::BigLaserie
+    }
+}
+
+fn wave_size(wave: usize) -> usize {
+    4 + wave * 2
+}
+
+fn count_bosses_defeated(
+    mut mode_state: ResMut<ModeState>,
+    mut scoreboard_events: EventReader<ScoreboardEvent>,
+) {
+    for event in scoreboard_events.iter() {
+        if let ScoreboardEvent::Kill(kind) = event {
+            if kind.is_big() {
+                mode_state.bosses_defeated += 1;
+            }
+        }
+    }
+}
+
+/// Moves waves along and ends the run once the mode's goal is reached
+fn update_mode(
+    clock: Res<GameClock>,
+    mode: Res<GameMode>,
+    mut mode_state: ResMut<ModeState>,
+    enemy_spawn_timer: Res<EnemySpawnTimer>,
+    enemies: Query<(), (With<Enemy>, With<Alive>)>,
+    scoreboards: Query<&Scoreboard>,
+    mut scoreboard_events: EventWriter<ScoreboardEvent>,
+) {
+    // The run starts when the player first fires, which unpauses the spawn timer
+    if mode_state.outcome.is_some() || enemy_spawn_timer.0.paused() {
+        return;
+    }
+    let time_survived = scoreboards
+        .iter()
+        .next()
+        .map_or(0.0, |scoreboard| scoreboard.time_survived);
+    match *mode {
+        GameMode::Survival => (),
+        GameMode::TimeAttack => {
+            if time_survived >= TIME_ATTACK_LENGTH {
+                mode_state.outcome = Some(RunOutcome::TimeUp);
+            }
+        }
+        GameMode::Waves => {
+            if mode_state.wave == 0 {
+                mode_state.wave = 1;
+                mode_state.wave_remaining = wave_size(1);
+            } else if mode_state.wave_break > 0.0 {
+                mode_state.wave_break -= clock.delta_seconds();
+                if mode_state.wave_break <= 0.0 {
+                    mode_state.wave_break = 0.0;
+                    mode_state.wave += 1;
+                    mode_state.wave_remaining = wave_size(mode_state.wave);
+                }
+            } else if mode_state.wave_remaining == 0 && enemies.iter().next().is_none() {
+                scoreboard_events.send(ScoreboardEvent::Bonus(WAVE_BONUS * mode_state.wave));
+                if mode_state.wave >= FINAL_WAVE {
+                    mode_state.outcome = Some(RunOutcome::Won);
+                } else {
+                    mode_state.wave_break = WAVE_BREAK;
+                }
+            }
+        }
+        GameMode::BossRush => {
+            if mode_state.bosses_defeated >= BOSS_RUSH_BOSSES {
+                let seconds_saved = (BOSS_RUSH_PAR - time_survived).max(0.0);
+                scoreboard_events.send(ScoreboardEvent::Bonus((seconds_saved * 10.0) as usize));
+                mode_state.outcome = Some(RunOutcome::Won);
+            }
+        }
+    }
+}
+
+/// Ends the run when the player dies or the game mode is over
+fn detect_game_over(
+    mut state: ResMut<State<GameState>>,
+    mut mode_state: ResMut<ModeState>,
+    players: Query<&Player, Changed<Player>>,
+    mut pending: Local<bool>,
+) {
+    if mode_state.outcome.is_none() && players.iter().any(|player| player.health <= 0.0) {
+        mode_state.outcome = Some(RunOutcome::Died);
+    }
+    // Only on change, after a retry this can run before the reset clears the outcome
+    if mode_state.is_changed() {
+        *pending = mode_state.outcome.is_some();
+    }
+    // Pushing fails when another change is already queued, so keep trying on later frames
+    if *pending && state.push(GameState::GameOver).is_ok() {
+        *pending = false;
+    }
+}
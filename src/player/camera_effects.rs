@@ -0,0 +1,218 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_egui::egui::Ui;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::custom_material::slider;
+use crate::enemies::Dead;
+use crate::GameClock;
+
+use super::controller::CharacterController;
+use super::zoom::Zoom;
+use super::{Footsteps, HitKind, InputState, MovementSettings, Player, PlayerCam, PlayerEvent};
+
+/// Largest shake rotation at full trauma, in radians
+const MAX_SHAKE_ANGLE: f32 = 0.06;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+/// Explosions further away than this don't shake the camera
+const EXPLOSION_SHAKE_RANGE: f32 = 80.0;
+/// Hits from further away than this shake the camera the least
+const HIT_SHAKE_RANGE: f32 = 60.0;
+const HEAD_BOB_HEIGHT: f32 = 0.06;
+const SPRINT_FOV_KICK: f32 = 10.0;
+
+/// Per effect intensity, from 0.0 (off) to 2.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraEffectSettings {
+    /// Turns every camera effect off, for motion sensitive players
+    pub enabled: bool,
+    pub shake: f32,
+    pub recoil: f32,
+    pub head_bob: f32,
+    pub fov_kick: f32,
+}
+
+impl Default for CameraEffectSettings {
+    fn default() -> Self {
+        CameraEffectSettings {
+            enabled: true,
+            shake: 1.0,
+            recoil: 1.0,
+            head_bob: 1.0,
+            fov_kick: 1.0,
+        }
+    }
+}
+
+impl CameraEffectSettings {
+    pub fn build_ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.enabled, "Camera effects");
+        if self.enabled {
+            slider(ui, &mut self.shake, 0.0..=2.0, "Screen Shake");
+            slider(ui, &mut self.recoil, 0.0..=2.0, "Recoil");
+            slider(ui, &mut self.head_bob, 0.0..=2.0, "Head Bob");
+            slider(ui, &mut self.fov_kick, 0.0..=2.0, "Sprint FOV Kick");
+        }
+    }
+
+    fn intensity(&self, effect: f32) -> f32 {
+        if self.enabled {
+            effect
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Transient camera state layered on top of the look pitch and camera height
+#[derive(Component)]
+pub struct CameraEffects {
+    /// 0.0 to 1.0, shake grows with the square of this
+    pub trauma: f32,
+    /// Upward pitch kick in radians
+    recoil: f32,
+    bob_weight: f32,
+    last_step_distance: f32,
+    pub base_fov: f32,
+    fov_kick: f32,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        CameraEffects {
+            trauma: 0.0,
+            recoil: 0.0,
+            bob_weight: 0.0,
+            last_step_distance: 0.0,
+            base_fov: (75.0f32).to_radians(),
+            fov_kick: 0.0,
+        }
+    }
+}
+
+impl CameraEffects {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+}
+
+/// Scales an effect down linearly to nothing at `range`
+fn falloff(distance: f32, range: f32) -> f32 {
+    (1.0 - distance / range).clamp(0.0, 1.0)
+}
+
+/// Adds trauma and recoil from hits, shots and nearby explosions
+pub fn camera_trauma(
+    clock: Res<GameClock>,
+    settings: Res<CameraEffectSettings>,
+    mut player_events: EventReader<PlayerEvent>,
+    players: Query<&Transform, With<Player>>,
+    new_dead: Query<&Transform, (Added<Dead>, Without<Player>)>,
+    mut cameras: Query<&mut CameraEffects>,
+) {
+    let player_position = players
+        .iter()
+        .next()
+        .map_or(Vec3::ZERO, |transform| transform.translation);
+    for mut effects in cameras.iter_mut() {
+        for player_event in player_events.iter() {
+            match *player_event {
+                PlayerEvent::Hit { kind, source, .. } => {
+                    let scale = falloff(player_position.distance(source), HIT_SHAKE_RANGE)
+                        .max(0.25)
+                        * settings.intensity(settings.shake);
+                    let trauma = match kind {
+                        // Lasers hit every frame while on target
+                        HitKind::Laser => 0.8 * clock.delta_seconds(),
+                        HitKind::Projectile => 0.35,
+                        HitKind::Splash => 0.45,
+                    };
+                    effects.add_trauma(trauma * scale);
+                }
+                PlayerEvent::Fire { alt, .. } => {
+                    let kick = if alt { 0.4 } else { 2.5 };
+                    effects.recoil += kick.to_radians() * settings.intensity(settings.recoil);
+                }
+                PlayerEvent::ShieldBroken => {
+                    effects.add_trauma(0.3 * settings.intensity(settings.shake));
+                }
+                PlayerEvent::Ultimate { .. } => {
+                    effects.add_trauma(0.6 * settings.intensity(settings.shake));
+                }
+                PlayerEvent::Footstep { .. }
+                | PlayerEvent::Jump
+                | PlayerEvent::Land { .. }
+                | PlayerEvent::LaserImpact { .. } => {}
+            }
+        }
+        for transform in new_dead.iter() {
+            let distance = player_position.distance(transform.translation);
+            effects.add_trauma(
+                0.5 * falloff(distance, EXPLOSION_SHAKE_RANGE) * settings.intensity(settings.shake),
+            );
+        }
+    }
+}
+
+/// Applies shake, recoil, head bob and FOV kick on top of the camera's look pitch
+pub fn apply_camera_effects(
+    clock: Res<GameClock>,
+    settings: Res<CameraEffectSettings>,
+    movement_settings: Res<MovementSettings>,
+    state: Res<InputState>,
+    players: Query<(&CharacterController, &Footsteps), With<Player>>,
+    mut cameras: Query<
+        (
+            &mut Transform,
+            &mut PerspectiveProjection,
+            &mut CameraEffects,
+            &Zoom,
+        ),
+        With<PlayerCam>,
+    >,
+) {
+    let (controller, footsteps) = match players.iter().next() {
+        Some(player) => player,
+        None => return,
+    };
+    let dt = clock.delta_seconds();
+    let t = clock.seconds();
+    for (mut transform, mut projection, mut effects, zoom) in cameras.iter_mut() {
+        effects.trauma = (effects.trauma - TRAUMA_DECAY * dt).max(0.0);
+        // Recover from recoil quickly, independent of frame rate
+        effects.recoil *= (-12.0 * dt).exp();
+
+        // Smooth noise from a few unrelated sine waves
+        let shake = effects.trauma * effects.trauma * MAX_SHAKE_ANGLE;
+        let shake_pitch = shake * ((t * 37.0).sin() + (t * 23.0 + 1.3).sin()) * 0.5;
+        let shake_yaw = shake * ((t * 31.0 + 0.7).sin() + (t * 19.0 + 2.1).sin()) * 0.5;
+        let shake_roll = shake * ((t * 29.0 + 3.1).sin() + (t * 17.0 + 0.4).sin()) * 0.5;
+        transform.rotation = Quat::from_axis_angle(Vec3::X, state.pitch + effects.recoil)
+            * Quat::from_euler(EulerRot::YXZ, shake_yaw, shake_pitch, shake_roll);
+
+        // Bob while footsteps are accumulating, fading in and out smoothly
+        let moving = controller.grounded && footsteps.move_distance != effects.last_step_distance;
+        effects.last_step_distance = footsteps.move_distance;
+        let target_weight = if moving { 1.0 } else { 0.0 };
+        effects.bob_weight += (target_weight - effects.bob_weight) * (1.0 - (-8.0 * dt).exp());
+        // Lowest point lines up with each footstep sound
+        let phase = PI * footsteps.move_distance / footsteps.stride;
+        let bob = -HEAD_BOB_HEIGHT
+            * phase.cos().powi(2)
+            * effects.bob_weight
+            * settings.intensity(settings.head_bob);
+        transform.translation.y = controller.camera_height() + bob;
+
+        let target_kick = if controller.sprinting {
+            SPRINT_FOV_KICK.to_radians() * settings.intensity(settings.fov_kick)
+        } else {
+            0.0
+        };
+        effects.fov_kick += (target_kick - effects.fov_kick) * (1.0 - (-6.0 * dt).exp());
+        effects.base_fov = movement_settings.fov.to_radians();
+        // The kick would fight the zoom, so it fades out while zooming in
+        projection.fov = zoom.fov(effects.base_fov) + effects.fov_kick * (1.0 - zoom.amount());
+    }
+}
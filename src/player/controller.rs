@@ -0,0 +1,319 @@
+use bevy::prelude::*;
+use heron::rapier_plugin::convert::IntoRapier;
+use heron::rapier_plugin::rapier3d::prelude::RigidBodySet;
+use heron::rapier_plugin::{PhysicsWorld, RigidBodyHandle, ShapeCastCollisionType};
+use heron::{CollisionLayers, CollisionShape};
+
+use crate::input::{Action, ActionState};
+use crate::{GameClock, Layer};
+
+use super::{Footsteps, MovementSettings, Player, PlayerEvent};
+
+/// How far below the capsule the ground probe reaches
+const GROUND_CHECK_DISTANCE: f32 = 0.15;
+/// Camera height above the body center when standing and crouching
+const STAND_CAMERA_HEIGHT: f32 = 1.82;
+const CROUCH_CAMERA_HEIGHT: f32 = 1.0;
+/// Shorter falls than this, in seconds, don't make a landing sound
+const LAND_MIN_AIR_TIME: f32 = 0.3;
+
+/// Physics driven movement state for the player body
+#[derive(Component)]
+pub struct CharacterController {
+    pub radius: f32,
+    pub stand_half_segment: f32,
+    pub crouch_half_segment: f32,
+    pub crouch_speed_multiplier: f32,
+    pub jump_velocity: f32,
+    /// Seconds after leaving the ground that a jump is still allowed
+    pub coyote_time: f32,
+    /// Gravity scale used while falling, so jumps don't feel floaty
+    pub fall_gravity_scale: f32,
+    /// Steepest walkable slope in radians
+    pub max_slope_angle: f32,
+    pub dash_speed: f32,
+    pub dash_duration: f32,
+    pub dash_cooldown: Timer,
+    pub grounded: bool,
+    pub ground_normal: Vec3,
+    pub crouching: bool,
+    pub sprinting: bool,
+    time_since_grounded: f32,
+    jumping: bool,
+    dash_time_left: f32,
+    dash_direction: Vec3,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        CharacterController {
+            radius: 0.5,
+            stand_half_segment: 1.0,
+            crouch_half_segment: 0.4,
+            crouch_speed_multiplier: 0.5,
+            jump_velocity: 7.0,
+            coyote_time: 0.15,
+            fall_gravity_scale: 2.5,
+            max_slope_angle: (45.0f32).to_radians(),
+            dash_speed: 40.0,
+            dash_duration: 0.15,
+            dash_cooldown: {
+                let mut timer = Timer::from_seconds(1.5, false);
+                // Allow a dash right away
+                timer.tick(timer.duration());
+                timer
+            },
+            grounded: false,
+            ground_normal: Vec3::Y,
+            crouching: false,
+            sprinting: false,
+            time_since_grounded: 0.0,
+            jumping: false,
+            dash_time_left: 0.0,
+            dash_direction: Vec3::ZERO,
+        }
+    }
+}
+
+impl CharacterController {
+    fn half_segment(&self) -> f32 {
+        if self.crouching {
+            self.crouch_half_segment
+        } else {
+            self.stand_half_segment
+        }
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.dash_time_left > 0.0
+    }
+
+    /// Distance from the body center down to the bottom of the capsule
+    pub fn feet_height(&self) -> f32 {
+        self.half_segment() + self.radius
+    }
+
+    /// Camera height above the body center
+    pub fn camera_height(&self) -> f32 {
+        if self.crouching {
+            CROUCH_CAMERA_HEIGHT
+        } else {
+            STAND_CAMERA_HEIGHT
+        }
+    }
+}
+
+fn world_query_layers() -> CollisionLayers {
+    CollisionLayers::none()
+        .with_group(Layer::Raycast)
+        .with_masks([Layer::World])
+}
+
+/// Casts a sphere down from the bottom of the capsule to find walkable ground
+pub fn ground_check(
+    clock: Res<GameClock>,
+    physics_world: PhysicsWorld,
+    mut player_events: EventWriter<PlayerEvent>,
+    mut players: Query<(Entity, &Transform, &mut CharacterController), With<Player>>,
+) {
+    for (entity, transform, mut controller) in players.iter_mut() {
+        let probe = CollisionShape::Sphere {
+            radius: controller.radius * 0.9,
+        };
+        let start = transform.translation - Vec3::Y * controller.half_segment();
+        let hit = physics_world.shape_cast_with_filter(
+            &probe,
+            start,
+            Quat::IDENTITY,
+            -Vec3::Y * (controller.radius * 0.1 + GROUND_CHECK_DISTANCE),
+            world_query_layers(),
+            |other| other != entity,
+        );
+
+        let (grounded, normal) = match hit {
+            Some(ShapeCastCollisionType::Collided(info)) => {
+                let normal = info.normal.normalize_or_zero();
+                (
+                    normal.angle_between(Vec3::Y) <= controller.max_slope_angle,
+                    normal,
+                )
+            }
+            Some(ShapeCastCollisionType::AlreadyPenetrating(..)) => (true, Vec3::Y),
+            None => (false, Vec3::Y),
+        };
+
+        controller.ground_normal = normal;
+        // Don't snap back to the ground on the frame a jump starts
+        let was_grounded = controller.grounded;
+        controller.grounded = grounded && !controller.jumping;
+        if controller.grounded
+            && !was_grounded
+            && controller.time_since_grounded > LAND_MIN_AIR_TIME
+        {
+            player_events.send(PlayerEvent::Land {
+                air_time: controller.time_since_grounded,
+            });
+        }
+        if controller.grounded {
+            controller.time_since_grounded = 0.0;
+        } else {
+            controller.time_since_grounded += clock.delta_seconds();
+            if controller.time_since_grounded > controller.coyote_time {
+                controller.jumping = false;
+            }
+        }
+    }
+}
+
+/// Handles movement actions and moves the physics body
+pub fn player_move(
+    action_state: Res<ActionState>,
+    clock: Res<GameClock>,
+    settings: Res<MovementSettings>,
+    mut rigid_bodies: ResMut<RigidBodySet>,
+    mut player_events: EventWriter<PlayerEvent>,
+    mut query: Query<
+        (
+            &Transform,
+            &RigidBodyHandle,
+            &mut CharacterController,
+            &mut Footsteps,
+        ),
+        With<Player>,
+    >,
+) {
+    for (transform, rb, mut controller, mut footsteps) in query.iter_mut() {
+        let body = match rigid_bodies.get_mut(rb.into_rapier()) {
+            Some(body) => body,
+            None => continue,
+        };
+        controller.dash_cooldown.tick(clock.delta());
+
+        let local_z = transform.local_z();
+        let local_z_y = if settings.lock_y { 0.0 } else { local_z.y };
+        let forward = -Vec3::new(local_z.x, local_z_y, local_z.z);
+        let right = Vec3::new(local_z.z, 0.0, -local_z.x);
+        let move_axis = action_state.move_axis;
+        let wish_dir = forward * move_axis.y + right * move_axis.x;
+        let mut run = 1.0;
+        controller.sprinting =
+            action_state.pressed(Action::Sprint) && move_axis.y > 0.5 && !controller.crouching;
+        if controller.sprinting {
+            run = settings.run_multiplier
+        }
+        let jump = action_state.just_pressed(Action::Jump);
+        let dash = action_state.just_pressed(Action::Dash);
+        if controller.crouching {
+            run *= controller.crouch_speed_multiplier;
+        }
+
+        let linvel = body.linvel();
+        let mut velocity = Vec3::new(linvel.x, linvel.y, linvel.z);
+        let mut horizontal = wish_dir * run * settings.speed;
+
+        if controller.grounded {
+            // Follow the slope instead of walking into it or launching off it
+            let normal = controller.ground_normal;
+            let along_slope = (horizontal - normal * horizontal.dot(normal)).normalize_or_zero()
+                * horizontal.length();
+            horizontal = Vec3::new(along_slope.x, 0.0, along_slope.z);
+            velocity.y = along_slope.y;
+        }
+
+        if jump
+            && !controller.jumping
+            && !controller.crouching
+            && controller.time_since_grounded <= controller.coyote_time
+        {
+            velocity.y = controller.jump_velocity;
+            controller.jumping = true;
+            controller.grounded = false;
+            player_events.send(PlayerEvent::Jump);
+        }
+
+        if dash && controller.dash_cooldown.finished() {
+            controller.dash_direction = if wish_dir == Vec3::ZERO {
+                Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero()
+            } else {
+                wish_dir.normalize()
+            };
+            controller.dash_time_left = controller.dash_duration;
+            controller.dash_cooldown.reset();
+        }
+        if controller.is_dashing() {
+            controller.dash_time_left -= clock.delta_seconds();
+            horizontal = controller.dash_direction * controller.dash_speed;
+        }
+
+        velocity.x = horizontal.x;
+        velocity.z = horizontal.z;
+        if !settings.lock_y && !controller.grounded && wish_dir.y != 0.0 {
+            velocity.y = horizontal.y;
+        }
+
+        let gravity_scale = if !controller.grounded && velocity.y < 0.0 {
+            controller.fall_gravity_scale
+        } else {
+            1.0
+        };
+        body.set_gravity_scale(gravity_scale, true);
+        body.set_linvel([velocity.x, velocity.y, velocity.z].into(), true);
+
+        if controller.grounded {
+            footsteps.move_distance += horizontal.length() * clock.delta_seconds();
+        }
+    }
+}
+
+/// Resizes the capsule while crouch is held, if there is room to stand back up
+pub fn player_crouch(
+    action_state: Res<ActionState>,
+    physics_world: PhysicsWorld,
+    mut players: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut CollisionShape,
+            &mut CharacterController,
+        ),
+        With<Player>,
+    >,
+) {
+    let wants_crouch = action_state.pressed(Action::Crouch);
+    for (entity, mut transform, mut shape, mut controller) in players.iter_mut() {
+        if wants_crouch == controller.crouching {
+            continue;
+        }
+        let grow = controller.stand_half_segment - controller.crouch_half_segment;
+        if !wants_crouch {
+            // Check for a ceiling before standing back up
+            let probe = CollisionShape::Sphere {
+                radius: controller.radius * 0.9,
+            };
+            let blocked = physics_world
+                .shape_cast_with_filter(
+                    &probe,
+                    transform.translation + Vec3::Y * controller.crouch_half_segment,
+                    Quat::IDENTITY,
+                    Vec3::Y * grow * 2.0,
+                    world_query_layers(),
+                    |other| other != entity,
+                )
+                .is_some();
+            if blocked {
+                continue;
+            }
+        }
+
+        controller.crouching = wants_crouch;
+        *shape = CollisionShape::Capsule {
+            half_segment: controller.half_segment(),
+            radius: controller.radius,
+        };
+        if controller.crouching {
+            transform.translation.y -= grow;
+        } else {
+            transform.translation.y += grow;
+        }
+    }
+}
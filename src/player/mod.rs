@@ -0,0 +1,714 @@
+use bevy::prelude::*;
+use bevy_egui::egui::Ui;
+use heron::rapier_plugin::convert::IntoRapier;
+use heron::rapier_plugin::rapier3d::prelude::RigidBodySet;
+use heron::rapier_plugin::{PhysicsWorld, RigidBodyHandle};
+use heron::{
+    CollisionLayers, CollisionShape, PhysicMaterial, PhysicsLayer, RigidBody, RotationConstraints,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::assets::custom_material::slider;
+use crate::assets::GameState;
+use crate::enemies::{Enemy, EnemyDamaged, EnemySpawnTimer};
+use crate::input::{Action, ActionState};
+use crate::modes::difficulty::Difficulty;
+use crate::run::{ResetRun, RunAppExt};
+use crate::ui::scoreboard::ScoreboardEvent;
+use crate::world::LevelMesh;
+use crate::{GameClock, GameplaySystem, Layer};
+
+use self::camera_effects::{
+    apply_camera_effects, camera_trauma, CameraEffectSettings, CameraEffects,
+};
+use self::controller::{ground_check, player_crouch, player_move, CharacterController};
+use self::ultimate::{activate_ultimate, charge_ultimate, Ultimate, UltimateSettings};
+use self::zoom::{player_zoom, Zoom};
+
+pub mod camera_effects;
+pub mod controller;
+pub mod noclip;
+pub mod ultimate;
+pub mod visuals;
+pub mod zoom;
+
+/// Damage of the primary laser against the first enemy it hits
+const PRIMARY_DAMAGE: f32 = 1001.0;
+/// Damage multiplier applied for every enemy the primary laser passes through
+const PIERCE_DAMAGE_FALLOFF: f32 = 0.6;
+const PRIMARY_RANGE: f32 = 200.0;
+/// Distance walked between footstep sounds
+const STEP_DISTANCE: f32 = 4.0;
+/// Sprinting takes longer strides, so the step rate rises less than the speed
+const SPRINT_STRIDE_MULTIPLIER: f32 = 1.35;
+/// How far below the player's feet the surface probe reaches
+const SURFACE_PROBE_DISTANCE: f32 = 0.5;
+/// Where the player starts every run
+const SPAWN_POSITION: Vec3 = Vec3::new(0.0, 3.0, 100.0);
+
+/// First person movement, look and weapons
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_run_resource::<InputState>()
+            .init_run_resource::<TimeSinceHit>()
+            .init_resource::<MovementSettings>()
+            .init_resource::<CameraEffectSettings>()
+            .init_resource::<UltimateSettings>()
+            .add_event::<PlayerEvent>()
+            .add_system(reset_player.label(GameplaySystem::Reset))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_player))
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(apply_difficulty))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .label(GameplaySystem::Player)
+                    .after(GameplaySystem::Reset)
+                    .with_system(enable_ccd.before(player_move))
+                    .with_system(ground_check.before(player_move))
+                    .with_system(player_move)
+                    .with_system(player_crouch.after(player_move))
+                    .with_system(player_look.after(player_zoom).after(player_crouch))
+                    .with_system(player_zoom)
+                    .with_system(player_fire.after(player_look))
+                    .with_system(charge_ultimate.after(player_fire))
+                    .with_system(activate_ultimate.after(charge_ultimate).after(player_move))
+                    .with_system(regenerate_shield.after(activate_ultimate))
+                    .with_system(footsteps.after(regenerate_shield))
+                    .with_system(camera_trauma.after(footsteps))
+                    .with_system(
+                        apply_camera_effects
+                            .after(camera_trauma)
+                            .after(player_look)
+                            .after(player_crouch)
+                            .after(player_fire),
+                    ),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitKind {
+    Laser,
+    Projectile,
+    Splash,
+}
+
+/// The lasergun's two fire modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WeaponKind {
+    Primary,
+    Secondary,
+}
+
+impl WeaponKind {
+    pub fn all() -> [WeaponKind; 2] {
+        [WeaponKind::Primary, WeaponKind::Secondary]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WeaponKind::Primary => "Primary",
+            WeaponKind::Secondary => "Secondary",
+        }
+    }
+}
+
+pub enum PlayerEvent {
+    /// `source` is where the damage came from, `direction` is the direction it was travelling
+    Hit {
+        kind: HitKind,
+        source: Vec3,
+        direction: Vec3,
+    },
+    ShieldBroken,
+    /// A laser beam from the weapon at `from` to where it stopped at `to`
+    Fire {
+        alt: bool,
+        from: Vec3,
+        to: Vec3,
+    },
+    /// The primary laser hit something at `position`
+    LaserImpact {
+        position: Vec3,
+    },
+    /// `surface` is the level mesh under the player, if the probe found one
+    Footstep {
+        surface: Option<LevelMesh>,
+        sprinting: bool,
+    },
+    Jump,
+    /// `air_time` is how long the player was off the ground, in seconds
+    Land {
+        air_time: f32,
+    },
+    /// The ultimate's shockwave went off around `center`
+    Ultimate {
+        center: Vec3,
+    },
+}
+
+/// Keeps track of pitch and yaw
+#[derive(Default)]
+pub struct InputState {
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+/// Mouse and gamepad sensitivity and movement speed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MovementSettings {
+    pub horizontal_sensitivity: f32,
+    pub vertical_sensitivity: f32,
+    pub invert_y: bool,
+    /// 0.0 is raw input, higher values average mouse movement over more frames
+    pub mouse_smoothing: f32,
+    /// Extra turn for fast mouse movement, 0.0 is off
+    pub mouse_acceleration: f32,
+    /// Furthest the camera can look up or down, in degrees
+    pub pitch_limit: f32,
+    /// Vertical field of view in degrees
+    pub fov: f32,
+    pub gamepad_sensitivity: f32,
+    pub gamepad_deadzone: f32,
+    pub gamepad_response_curve: f32,
+    pub speed: f32,
+    pub run_multiplier: f32,
+    pub lock_y: bool,
+    /// Zoom stays on until pressed again instead of only while held
+    pub zoom_toggle: bool,
+}
+
+impl Default for MovementSettings {
+    fn default() -> Self {
+        Self {
+            horizontal_sensitivity: 3.0,
+            vertical_sensitivity: 3.0,
+            invert_y: false,
+            mouse_smoothing: 0.0,
+            mouse_acceleration: 0.0,
+            pitch_limit: 88.0,
+            fov: 75.0,
+            gamepad_sensitivity: 3.0,
+            gamepad_deadzone: 0.15,
+            gamepad_response_curve: 2.0,
+            speed: 14.0,
+            lock_y: true,
+            run_multiplier: 1.6,
+            zoom_toggle: false,
+        }
+    }
+}
+
+impl MovementSettings {
+    pub fn build_ui(&mut self, ui: &mut Ui) {
+        // TODO refactor after jam
+        slider(
+            ui,
+            &mut self.horizontal_sensitivity,
+            0.1..=10.0,
+            "Horizontal Mouse Sensitivity",
+        );
+        slider(
+            ui,
+            &mut self.vertical_sensitivity,
+            0.1..=10.0,
+            "Vertical Mouse Sensitivity",
+        );
+        ui.checkbox(&mut self.invert_y, "Invert Y");
+        slider(ui, &mut self.mouse_smoothing, 0.0..=0.9, "Mouse Smoothing");
+        slider(
+            ui,
+            &mut self.mouse_acceleration,
+            0.0..=1.0,
+            "Mouse Acceleration",
+        );
+        slider(ui, &mut self.pitch_limit, 45.0..=89.0, "Pitch Limit");
+        slider(ui, &mut self.fov, 50.0..=110.0, "Field of View");
+        slider(
+            ui,
+            &mut self.gamepad_sensitivity,
+            0.1..=10.0,
+            "Gamepad Sensitivity",
+        );
+        slider(
+            ui,
+            &mut self.gamepad_deadzone,
+            0.0..=0.5,
+            "Gamepad Deadzone",
+        );
+        slider(
+            ui,
+            &mut self.gamepad_response_curve,
+            1.0..=4.0,
+            "Gamepad Response Curve",
+        );
+        ui.checkbox(&mut self.zoom_toggle, "Toggle zoom");
+    }
+}
+
+#[derive(Bundle)]
+struct PlayerBundle {
+    player: Player,
+    ultimate: Ultimate,
+    controller: CharacterController,
+    footsteps: Footsteps,
+    transform: Transform,
+    global_tranform: GlobalTransform,
+    rigid_body: RigidBody,
+    collision_layers: CollisionLayers,
+    collision_shape: CollisionShape,
+    rotation_constraints: RotationConstraints,
+    physic_material: PhysicMaterial,
+}
+
+impl Default for PlayerBundle {
+    fn default() -> Self {
+        PlayerBundle {
+            player: Player::default(),
+            ultimate: Ultimate::default(),
+            controller: CharacterController::default(),
+            footsteps: Footsteps::default(),
+            transform: Transform::from_translation(SPAWN_POSITION),
+            global_tranform: GlobalTransform::default(),
+            rigid_body: RigidBody::Dynamic,
+            collision_layers: CollisionLayers::from_bits(
+                Layer::Player.to_bits(),
+                Layer::all_bits(),
+            )
+            .with_group(Layer::Player)
+            .with_masks([Layer::Bullet, Layer::Enemy, Layer::World]),
+            collision_shape: CollisionShape::Capsule {
+                half_segment: 1.0,
+                radius: 0.5,
+            },
+            rotation_constraints: RotationConstraints::lock(),
+            physic_material: PhysicMaterial {
+                restitution: 0.0,
+                density: 10.0,
+                // The controller sets the velocity directly, friction would only stick us to walls
+                friction: 0.0,
+            },
+        }
+    }
+}
+
+impl PlayerBundle {
+    fn with_difficulty(difficulty: Difficulty) -> Self {
+        let mut bundle = PlayerBundle::default();
+        bundle.player.set_difficulty(difficulty);
+        bundle.player.reset();
+        bundle
+    }
+}
+
+#[derive(Component)]
+pub struct Player {
+    pub health: f32,
+    pub max_health: f32,
+    /// Absorbs damage before health
+    pub shield: f32,
+    pub max_shield: f32,
+    /// Seconds without taking damage before the shield starts regenerating
+    pub shield_regen_delay: f32,
+    /// Shield regenerated per second
+    pub shield_regen_rate: f32,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player {
+            health: 1000.0,
+            max_health: 1000.0,
+            shield: 500.0,
+            max_shield: 500.0,
+            shield_regen_delay: 3.0,
+            shield_regen_rate: 150.0,
+        }
+    }
+}
+
+impl Player {
+    /// Applies damage to the shield first and the remainder to health.
+    /// Returns true if this damage broke the shield.
+    pub fn take_damage(&mut self, damage: f32) -> bool {
+        let had_shield = self.shield > 0.0;
+        let absorbed = damage.min(self.shield);
+        self.shield -= absorbed;
+        self.health -= damage - absorbed;
+        had_shield && self.shield <= 0.0
+    }
+
+    /// Restores health and shield, used when restarting
+    pub fn reset(&mut self) {
+        self.health = self.max_health;
+        self.shield = self.max_shield;
+    }
+
+    /// Scales the maximum health and shield, call [`Player::reset`] to fill them up
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        let defaults = Player::default();
+        self.max_health = defaults.max_health * difficulty.player_health_scale();
+        self.max_shield = defaults.max_shield * difficulty.player_health_scale();
+    }
+}
+
+#[derive(Component)]
+struct Footsteps {
+    move_distance: f32,
+    /// Distance between steps, longer while sprinting
+    stride: f32,
+}
+
+impl Default for Footsteps {
+    fn default() -> Self {
+        Footsteps {
+            move_distance: 0.0,
+            stride: STEP_DISTANCE,
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct PlayerCam;
+
+#[derive(Component)]
+pub struct PlayerWeapon {
+    fire_rate: f32,
+    last_shot: f32,
+    secondary_fire_rate: f32,
+    secondary_fire_last_shot: f32,
+}
+
+/// Spawns the `Camera3dBundle` to be controlled
+fn setup_player(mut commands: Commands, difficulty: Res<Difficulty>) {
+    commands
+        .spawn_bundle(PlayerBundle::with_difficulty(*difficulty))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(PerspectiveCameraBundle {
+                    transform: Transform::from_xyz(0.0, 1.82, 0.0),
+                    perspective_projection: PerspectiveProjection {
+                        fov: (75.0f32).to_radians(),
+                        aspect_ratio: 1.0,
+                        near: 0.1,
+                        far: 1000.0,
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle((
+                            Transform::from_xyz(0.28, -0.14, -0.12),
+                            GlobalTransform::identity(),
+                        ))
+                        .insert(PlayerWeapon {
+                            fire_rate: 0.5, //120RPM
+                            last_shot: 0.0,
+                            secondary_fire_rate: 0.08, //750RPM
+                            secondary_fire_last_shot: 0.0,
+                        });
+                })
+                .insert(PlayerCam)
+                .insert(CameraEffects::default())
+                .insert(Zoom::default());
+        });
+}
+
+/// Gives the player the health of a difficulty picked on the menu, the run has been reset by then
+fn apply_difficulty(difficulty: Res<Difficulty>, mut players: Query<&mut Player>) {
+    if !difficulty.is_changed() {
+        return;
+    }
+    for mut player in players.iter_mut() {
+        player.set_difficulty(*difficulty);
+        player.reset();
+    }
+}
+
+/// Puts the player back at the start with full health and nothing carried over from the last run
+fn reset_player(
+    mut reset_events: EventReader<ResetRun>,
+    mut rigid_bodies: ResMut<RigidBodySet>,
+    mut players: Query<(
+        &mut Player,
+        &mut Ultimate,
+        &mut CharacterController,
+        &mut Footsteps,
+        &mut Transform,
+        Option<&RigidBodyHandle>,
+    )>,
+    mut cameras: Query<(&mut CameraEffects, &mut Zoom), With<PlayerCam>>,
+) {
+    if reset_events.iter().count() == 0 {
+        return;
+    }
+    for (mut player, mut ultimate, mut controller, mut footsteps, mut transform, handle) in
+        players.iter_mut()
+    {
+        player.reset();
+        *ultimate = Ultimate::default();
+        *controller = CharacterController::default();
+        *footsteps = Footsteps::default();
+        *transform = Transform::from_translation(SPAWN_POSITION);
+        if let Some(body) = handle.and_then(|handle| rigid_bodies.get_mut(handle.into_rapier())) {
+            body.set_translation(
+                [SPAWN_POSITION.x, SPAWN_POSITION.y, SPAWN_POSITION.z].into(),
+                true,
+            );
+            body.set_linvel([0.0, 0.0, 0.0].into(), true);
+        }
+    }
+    for (mut effects, mut zoom) in cameras.iter_mut() {
+        *effects = CameraEffects::default();
+        *zoom = Zoom::default();
+    }
+}
+
+fn enable_ccd(
+    mut rigid_bodies: ResMut<RigidBodySet>,
+    new_handles: Query<&RigidBodyHandle, (With<Player>, Added<RigidBodyHandle>)>,
+) {
+    for handle in new_handles.iter() {
+        if let Some(body) = rigid_bodies.get_mut(handle.into_rapier()) {
+            body.enable_ccd(true);
+        }
+    }
+}
+
+/// Applies the look actions to the player yaw and camera pitch
+fn player_look(
+    action_state: Res<ActionState>,
+    settings: Res<MovementSettings>,
+    mut state: ResMut<InputState>,
+    zooms: Query<(&Zoom, &CameraEffects)>,
+    mut query: ParamSet<(
+        Query<&mut Transform, With<Player>>,
+        Query<&mut Transform, With<PlayerCam>>,
+    )>,
+) {
+    if action_state.look_delta == Vec2::ZERO {
+        return;
+    }
+    let look_delta = action_state.look_delta
+        * zooms.iter().next().map_or(1.0, |(zoom, effects)| {
+            zoom.sensitivity_scale(effects.base_fov)
+        });
+    state.yaw += look_delta.x;
+    let pitch_limit = settings.pitch_limit.to_radians();
+    state.pitch = (state.pitch + look_delta.y).clamp(-pitch_limit, pitch_limit);
+
+    // Update player yaw
+    for mut transform in query.p0().iter_mut() {
+        transform.rotation = Quat::from_axis_angle(Vec3::Y, state.yaw);
+    }
+
+    // Update camera pitch
+    for mut transform in query.p1().iter_mut() {
+        transform.rotation = Quat::from_axis_angle(Vec3::X, state.pitch);
+    }
+}
+
+fn player_fire(
+    clock: Res<GameClock>,
+    action_state: Res<ActionState>,
+    physics_world: PhysicsWorld,
+    state: Res<InputState>,
+    player_cams: Query<&GlobalTransform, With<PlayerCam>>,
+    mut player_weapon: Query<(&GlobalTransform, &mut PlayerWeapon)>,
+    // Grouped to stay within the system parameter limit
+    (mut player_events, mut scoreboard_events, mut damage_events): (
+        EventWriter<PlayerEvent>,
+        EventWriter<ScoreboardEvent>,
+        EventWriter<EnemyDamaged>,
+    ),
+    mut enemies: Query<&mut Enemy>,
+    mut enemy_spawn_timer: ResMut<EnemySpawnTimer>,
+    player: Query<&Player>,
+) {
+    if let Some(player) = player.iter().next() {
+        if player.health <= 0.0 {
+            return;
+        }
+        if action_state.pressed(Action::AltFire) {
+            enemy_spawn_timer.0.unpause();
+            for (cam_transform, (weapon_transform, mut weapon)) in
+                player_cams.iter().zip(player_weapon.iter_mut())
+            {
+                if clock.seconds() - weapon.secondary_fire_last_shot < weapon.secondary_fire_rate {
+                    return;
+                } else {
+                    weapon.secondary_fire_last_shot = clock.seconds();
+                };
+
+                let pitch = state.pitch;
+                let yaw = -state.yaw;
+                let xz = f32::cos(pitch);
+                let looking_dir =
+                    -Vec3::new(-xz * f32::sin(yaw), -f32::sin(pitch), xz * f32::cos(yaw));
+                player_events.send(PlayerEvent::Fire {
+                    alt: true,
+                    from: weapon_transform.translation + weapon_transform.forward() * 0.6,
+                    to: weapon_transform.translation + weapon_transform.forward() * 100.0,
+                });
+
+                if let Some(collision) = physics_world.ray_cast_with_filter(
+                    cam_transform.translation,
+                    looking_dir * 100.0,
+                    true,
+                    CollisionLayers::none()
+                        .with_group(Layer::Raycast)
+                        .with_masks([Layer::World, Layer::Enemy]),
+                    |_| true,
+                ) {
+                    // TODO move to be triggered by event
+                    if let Ok(mut enemy) = enemies.get_mut(collision.entity) {
+                        enemy.health -= 334;
+                        damage_events.send(EnemyDamaged {
+                            entity: collision.entity,
+                            amount: 334,
+                        });
+                        scoreboard_events.send(ScoreboardEvent::Hit(WeaponKind::Secondary));
+                    } else {
+                        scoreboard_events.send(ScoreboardEvent::Miss {
+                            weapon: WeaponKind::Secondary,
+                            hit_world: true,
+                        });
+                    }
+                } else {
+                    scoreboard_events.send(ScoreboardEvent::Miss {
+                        weapon: WeaponKind::Secondary,
+                        hit_world: false,
+                    });
+                }
+            }
+        } else if action_state.just_pressed(Action::Fire) {
+            enemy_spawn_timer.0.unpause();
+            for (cam_transform, (weapon_transform, mut weapon)) in
+                player_cams.iter().zip(player_weapon.iter_mut())
+            {
+                if clock.seconds() - weapon.last_shot < weapon.fire_rate {
+                    return;
+                } else {
+                    weapon.last_shot = clock.seconds();
+                };
+
+                let pitch = state.pitch;
+                let yaw = -state.yaw;
+                let xz = f32::cos(pitch);
+                let looking_dir =
+                    -Vec3::new(-xz * f32::sin(yaw), -f32::sin(pitch), xz * f32::cos(yaw));
+
+                // Pierce through enemies until the beam reaches the level
+                let mut damage = PRIMARY_DAMAGE;
+                let mut pierced = Vec::new();
+                let mut hit_world = false;
+                let mut impact_point = cam_transform.translation + looking_dir * PRIMARY_RANGE;
+                while let Some(collision) = physics_world.ray_cast_with_filter(
+                    cam_transform.translation,
+                    looking_dir * PRIMARY_RANGE,
+                    true,
+                    CollisionLayers::none()
+                        .with_group(Layer::Raycast)
+                        .with_masks([Layer::World, Layer::Enemy]),
+                    |entity| !pierced.contains(&entity),
+                ) {
+                    impact_point = collision.collision_point;
+                    player_events.send(PlayerEvent::LaserImpact {
+                        position: impact_point,
+                    });
+                    // TODO move to be triggered by event
+                    if let Ok(mut enemy) = enemies.get_mut(collision.entity) {
+                        enemy.health -= damage.round() as i32;
+                        damage_events.send(EnemyDamaged {
+                            entity: collision.entity,
+                            amount: damage.round() as i32,
+                        });
+                        damage *= PIERCE_DAMAGE_FALLOFF;
+                        pierced.push(collision.entity);
+                    } else {
+                        hit_world = true;
+                        break;
+                    }
+                }
+                scoreboard_events.send(if pierced.is_empty() {
+                    ScoreboardEvent::Miss {
+                        weapon: WeaponKind::Primary,
+                        hit_world,
+                    }
+                } else {
+                    ScoreboardEvent::Hit(WeaponKind::Primary)
+                });
+
+                player_events.send(PlayerEvent::Fire {
+                    alt: false,
+                    from: weapon_transform.translation + weapon_transform.forward() * 0.6,
+                    to: impact_point,
+                });
+            }
+        }
+    }
+}
+
+/// Seconds since the player last took a hit
+#[derive(Default)]
+struct TimeSinceHit(f32);
+
+/// Regenerates the shield once no hits have landed for `shield_regen_delay` seconds
+fn regenerate_shield(
+    clock: Res<GameClock>,
+    mut time_since_hit: ResMut<TimeSinceHit>,
+    mut player_events: EventReader<PlayerEvent>,
+    mut players: Query<&mut Player>,
+) {
+    time_since_hit.0 += clock.delta_seconds();
+    for player_event in player_events.iter() {
+        if let PlayerEvent::Hit { .. } = player_event {
+            time_since_hit.0 = 0.0;
+        }
+    }
+    for mut player in players.iter_mut() {
+        if player.health <= 0.0 {
+            continue;
+        }
+        if time_since_hit.0 > player.shield_regen_delay && player.shield < player.max_shield {
+            player.shield = (player.shield + player.shield_regen_rate * clock.delta_seconds())
+                .min(player.max_shield);
+        }
+    }
+}
+
+/// Sends a footstep for every stride walked, with the surface found under the player
+fn footsteps(
+    physics_world: PhysicsWorld,
+    mut player_events: EventWriter<PlayerEvent>,
+    mut players: Query<(Entity, &Transform, &CharacterController, &mut Footsteps)>,
+    level_meshes: Query<&LevelMesh>,
+) {
+    for (entity, transform, controller, mut footsteps) in players.iter_mut() {
+        footsteps.stride = if controller.sprinting {
+            STEP_DISTANCE * SPRINT_STRIDE_MULTIPLIER
+        } else {
+            STEP_DISTANCE
+        };
+        if footsteps.move_distance <= footsteps.stride {
+            continue;
+        }
+        footsteps.move_distance -= footsteps.stride;
+        let surface = physics_world
+            .ray_cast_with_filter(
+                transform.translation,
+                -Vec3::Y * (controller.feet_height() + SURFACE_PROBE_DISTANCE),
+                true,
+                CollisionLayers::none()
+                    .with_group(Layer::Raycast)
+                    .with_masks([Layer::World]),
+                |other| other != entity,
+            )
+            .and_then(|hit| level_meshes.get(hit.entity).ok().copied());
+        player_events.send(PlayerEvent::Footstep {
+            surface,
+            sprinting: controller.sprinting,
+        });
+    }
+}
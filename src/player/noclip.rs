@@ -0,0 +1,115 @@
+use bevy::{
+    input::mouse::MouseWheel,
+    prelude::*,
+    render::camera::{ActiveCamera, Camera3d},
+};
+
+use crate::{
+    input::{Action, ActionState},
+    run::RunScoped,
+    GameClock,
+};
+
+use super::{MovementSettings, PlayerCam};
+
+/// Debug camera that flies freely while the player body stays where it is, gone with the run
+pub struct Noclip {
+    camera: Option<Entity>,
+    yaw: f32,
+    pitch: f32,
+    /// Units per second, changed with the mouse wheel
+    pub speed: f32,
+}
+
+impl Default for Noclip {
+    fn default() -> Self {
+        Noclip {
+            camera: None,
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 20.0,
+        }
+    }
+}
+
+impl Noclip {
+    pub fn is_active(&self) -> bool {
+        self.camera.is_some()
+    }
+}
+
+/// Spawns a free camera where the player is looking, or despawns it to return to the player view
+pub fn toggle_noclip(
+    mut commands: Commands,
+    action_state: Res<ActionState>,
+    mut noclip: ResMut<Noclip>,
+    mut active_camera: ResMut<ActiveCamera<Camera3d>>,
+    player_cams: Query<(&GlobalTransform, &PerspectiveProjection), With<PlayerCam>>,
+) {
+    if !action_state.just_pressed(Action::Noclip) {
+        return;
+    }
+    if let Some(camera) = noclip.camera.take() {
+        // The player camera becomes active again once this one is gone
+        commands.entity(camera).despawn_recursive();
+        return;
+    }
+    if let Some((cam_transform, projection)) = player_cams.iter().next() {
+        let forward = cam_transform.rotation * -Vec3::Z;
+        noclip.yaw = (-forward.x).atan2(-forward.z);
+        noclip.pitch = forward.y.clamp(-1.0, 1.0).asin();
+        let camera = commands
+            .spawn_bundle(PerspectiveCameraBundle {
+                transform: Transform::from_translation(cam_transform.translation).with_rotation(
+                    Quat::from_euler(EulerRot::YXZ, noclip.yaw, noclip.pitch, 0.0),
+                ),
+                perspective_projection: projection.clone(),
+                ..Default::default()
+            })
+            .insert(RunScoped)
+            .id();
+        active_camera.set(camera);
+        noclip.camera = Some(camera);
+    }
+}
+
+/// Flies the noclip camera with the movement actions and keeps them away from the player
+pub fn fly_noclip_camera(
+    clock: Res<GameClock>,
+    settings: Res<MovementSettings>,
+    mut noclip: ResMut<Noclip>,
+    mut action_state: ResMut<ActionState>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut cameras: Query<&mut Transform>,
+) {
+    let camera = match noclip.camera {
+        Some(camera) => camera,
+        None => return,
+    };
+    for ev in mouse_wheel.iter() {
+        noclip.speed = (ev.y + noclip.speed).max(0.0);
+    }
+    noclip.yaw += action_state.look_delta.x;
+    let pitch_limit = settings.pitch_limit.to_radians();
+    noclip.pitch = (noclip.pitch + action_state.look_delta.y).clamp(-pitch_limit, pitch_limit);
+
+    if let Ok(mut transform) = cameras.get_mut(camera) {
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, noclip.yaw, noclip.pitch, 0.0);
+        let mut velocity = transform.forward() * action_state.move_axis.y
+            + transform.right() * action_state.move_axis.x;
+        if action_state.pressed(Action::Jump) {
+            velocity += Vec3::Y;
+        }
+        if action_state.pressed(Action::Crouch) {
+            velocity -= Vec3::Y;
+        }
+        let mut speed = noclip.speed;
+        if action_state.pressed(Action::Sprint) {
+            speed *= settings.run_multiplier;
+        }
+        transform.translation += velocity.clamp_length_max(1.0) * speed * clock.delta_seconds();
+    }
+
+    // The player stands still while the camera is away, the rest of the game keeps running
+    action_state.release_all_except(&[Action::Menu, Action::Noclip]);
+}
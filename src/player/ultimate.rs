@@ -0,0 +1,125 @@
+use bevy::{prelude::*, utils::HashMap};
+use heron::rapier_plugin::{convert::IntoRapier, rapier3d::prelude::RigidBodySet, RigidBodyHandle};
+
+use crate::enemies::{Alive, Enemy, EnemyDamaged, EnemyKind};
+use crate::input::{Action, ActionState};
+use crate::ui::scoreboard::ScoreboardEvent;
+
+use super::{Player, PlayerEvent};
+
+/// How the ultimate charges and what the shockwave does
+pub struct UltimateSettings {
+    pub max_charge: f32,
+    pub kill_charge: HashMap<EnemyKind, f32>,
+    /// Charge for each point of damage dealt
+    pub damage_charge: f32,
+    pub radius: f32,
+    /// Damage at the center of the shockwave, falling off to nothing at `radius`
+    pub damage: f32,
+    /// Impulse pushing enemies away at the center, falling off like the damage
+    pub impulse: f32,
+}
+
+impl Default for UltimateSettings {
+    fn default() -> Self {
+        UltimateSettings {
+            max_charge: 100.0,
+            kill_charge: [
+                (EnemyKind::Orbie, 8.0),
+                (EnemyKind::Laserie, 8.0),
+                (EnemyKind::BigOrbie, 20.0),
+                (EnemyKind::BigLaserie, 20.0),
+            ]
+            .into_iter()
+            .collect(),
+            damage_charge: 0.002,
+            radius: 40.0,
+            damage: 2000.0,
+            impulse: 4000.0,
+        }
+    }
+}
+
+/// Charge toward the player's ultimate ability
+#[derive(Component, Default)]
+pub struct Ultimate {
+    pub charge: f32,
+}
+
+impl Ultimate {
+    pub fn fraction(&self, settings: &UltimateSettings) -> f32 {
+        (self.charge / settings.max_charge).clamp(0.0, 1.0)
+    }
+
+    pub fn is_ready(&self, settings: &UltimateSettings) -> bool {
+        self.charge >= settings.max_charge
+    }
+
+    fn add_charge(&mut self, amount: f32, settings: &UltimateSettings) {
+        self.charge = (self.charge + amount).min(settings.max_charge);
+    }
+}
+
+/// Fills the meter from kills and damage dealt
+pub fn charge_ultimate(
+    settings: Res<UltimateSettings>,
+    mut scoreboard_events: EventReader<ScoreboardEvent>,
+    mut damage_events: EventReader<EnemyDamaged>,
+    mut players: Query<(&Player, &mut Ultimate)>,
+) {
+    let mut charge = 0.0;
+    for scoreboard_event in scoreboard_events.iter() {
+        if let ScoreboardEvent::Kill(kind) = scoreboard_event {
+            charge += settings.kill_charge.get(kind).copied().unwrap_or(0.0);
+        }
+    }
+    for damage_event in damage_events.iter() {
+        charge += damage_event.amount.max(0) as f32 * settings.damage_charge;
+    }
+    if charge == 0.0 {
+        return;
+    }
+    for (player, mut ultimate) in players.iter_mut() {
+        if player.health > 0.0 {
+            ultimate.add_charge(charge, &settings);
+        }
+    }
+}
+
+/// Releases a shockwave that damages and pushes away nearby enemies once the meter is full
+pub fn activate_ultimate(
+    action_state: Res<ActionState>,
+    settings: Res<UltimateSettings>,
+    mut rigid_bodies: ResMut<RigidBodySet>,
+    mut player_events: EventWriter<PlayerEvent>,
+    mut players: Query<(&Transform, &Player, &mut Ultimate)>,
+    mut enemies: Query<(&Transform, &mut Enemy, &RigidBodyHandle), (With<Alive>, Without<Player>)>,
+) {
+    if !action_state.just_pressed(Action::Ultimate) {
+        return;
+    }
+    for (player_transform, player, mut ultimate) in players.iter_mut() {
+        if player.health <= 0.0 || !ultimate.is_ready(&settings) {
+            continue;
+        }
+        ultimate.charge = 0.0;
+        let center = player_transform.translation;
+        player_events.send(PlayerEvent::Ultimate { center });
+
+        for (enemy_transform, mut enemy, rb) in enemies.iter_mut() {
+            let offset = enemy_transform.translation - center;
+            let falloff = 1.0 - offset.length() / settings.radius;
+            if falloff <= 0.0 {
+                continue;
+            }
+            enemy.health -= (settings.damage * falloff).round() as i32;
+            if let Some(body) = rigid_bodies.get_mut(rb.into_rapier()) {
+                // Lift a little so enemies clear the floor
+                let push = (offset.normalize_or_zero() + Vec3::Y * 0.3).normalize_or_zero()
+                    * settings.impulse
+                    * falloff;
+                body.apply_impulse([push.x, push.y, push.z].into(), true);
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_polyline::{Polyline, PolylineBundle, PolylineMaterial};
+
+use crate::{
+    assets::{
+        orb_material::{OrbMaterial, OrbProperties},
+        GameState, ModelAssets,
+    },
+    enemies::visuals::Explosion,
+    input::ActionInputSystem,
+    run::{RunAppExt, RunScoped},
+};
+
+use super::{
+    noclip::{fly_noclip_camera, toggle_noclip, Noclip},
+    PlayerEvent, PlayerWeapon,
+};
+
+/// The lasergun, its beams and impacts, the crosshair, plus a noclip fly camera for debugging
+pub struct PlayerVisualsPlugin;
+
+impl Plugin for PlayerVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_run_resource::<Noclip>()
+            .add_system_to_stage(CoreStage::PreUpdate, toggle_noclip.after(ActionInputSystem))
+            .add_system_to_stage(CoreStage::PreUpdate, fly_noclip_camera.after(toggle_noclip))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(setup_player_visuals),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(add_weapon_model)
+                    .with_system(show_player_lasers)
+                    .with_system(update_player_polylines.after(show_player_lasers))
+                    .with_system(update_secondary_player_polylines.after(show_player_lasers))
+                    .with_system(spawn_player_explosions),
+            );
+    }
+}
+
+#[derive(Component)]
+struct PlayerPolyline;
+
+#[derive(Component)]
+struct PlayerPolylineSecondary;
+
+#[derive(Component, Deref, DerefMut)]
+pub struct PolylineTimer(Timer);
+
+/// Spawns the laser beams, hidden until the player fires, and the crosshair
+fn setup_player_visuals(
+    mut commands: Commands,
+    mut polylines: ResMut<Assets<Polyline>>,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    commands
+        .spawn_bundle(PolylineBundle {
+            polyline: polylines.add(Polyline {
+                vertices: vec![Vec3::ZERO, Vec3::ZERO],
+            }),
+            material: polyline_materials.add(PolylineMaterial {
+                width: 30.0,
+                color: Color::rgba(1.0, 0.0, 0.0, 0.9),
+                perspective: true,
+            }),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(PolylineTimer(Timer::new(
+            Duration::from_secs_f32(2.0),
+            false,
+        )))
+        .insert(PlayerPolyline);
+
+    commands
+        .spawn_bundle(PolylineBundle {
+            polyline: polylines.add(Polyline {
+                vertices: vec![Vec3::ZERO, Vec3::ZERO],
+            }),
+            material: polyline_materials.add(PolylineMaterial {
+                width: 10.0,
+                color: Color::rgba(1.0, 0.5, 0.0, 0.9),
+                perspective: true,
+            }),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(PolylineTimer(Timer::new(
+            Duration::from_secs_f32(2.0),
+            false,
+        )))
+        .insert(PlayerPolylineSecondary);
+
+    commands.spawn_bundle(ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(4.0), Val::Px(4.0)),
+            margin: Rect::all(Val::Auto),
+            ..Default::default()
+        },
+        color: Color::rgb(1.0, 1.0, 1.0).into(),
+        ..Default::default()
+    });
+}
+
+fn add_weapon_model(
+    mut commands: Commands,
+    model_assets: Res<ModelAssets>,
+    weapons: Query<Entity, Added<PlayerWeapon>>,
+) {
+    for entity in weapons.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn_scene(model_assets.lasergun.clone());
+        });
+    }
+}
+
+/// Lights up the beam of each shot, the update systems then fade it out
+fn show_player_lasers(
+    mut player_events: EventReader<PlayerEvent>,
+    mut polylines: ResMut<Assets<Polyline>>,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+    mut polylines_query: Query<
+        (
+            &Handle<Polyline>,
+            &mut Visibility,
+            &Handle<PolylineMaterial>,
+            &mut PolylineTimer,
+            Option<&PlayerPolylineSecondary>,
+        ),
+        Or<(With<PlayerPolyline>, With<PlayerPolylineSecondary>)>,
+    >,
+) {
+    for player_event in player_events.iter() {
+        if let PlayerEvent::Fire { alt, from, to } = *player_event {
+            for (polyline, mut visibility, material, mut timer, secondary) in
+                polylines_query.iter_mut()
+            {
+                if secondary.is_some() != alt {
+                    continue;
+                }
+                if let Some(polyline) = polylines.get_mut(polyline) {
+                    polyline.vertices[0] = from;
+                    polyline.vertices[1] = to;
+                }
+                if let Some(material) = polyline_materials.get_mut(material) {
+                    material.color.set_a(1.0);
+                }
+                visibility.is_visible = true;
+                timer.reset();
+            }
+        }
+    }
+}
+
+fn update_player_polylines(
+    time: Res<Time>,
+    mut polylines: ResMut<Assets<Polyline>>,
+    mut polylines_query: Query<
+        (
+            &mut Handle<Polyline>,
+            &mut Visibility,
+            &Handle<PolylineMaterial>,
+            &mut PolylineTimer,
+        ),
+        With<PlayerPolyline>,
+    >,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    for (polyline, mut visibility, material, mut timer) in polylines_query.iter_mut() {
+        timer.tick(time.delta());
+        let duration = timer.duration().as_secs_f32();
+        let elapsed = timer.elapsed().as_secs_f32();
+        let progress = (duration - elapsed) / duration;
+        if let Some(material) = polyline_materials.get_mut(material) {
+            if timer.just_finished() {
+                material.color.set_a(1.0);
+            } else {
+                // fade out laser over time
+                material.color.set_a(progress);
+            }
+        }
+        if timer.just_finished() {
+            visibility.is_visible = false;
+        }
+        if visibility.is_visible {
+            if let Some(polyline) = polylines.get_mut(&*polyline) {
+                let beam = polyline.vertices[1] - polyline.vertices[0];
+                //move start of laser out along normal over time, without passing the impact point
+                polyline.vertices[0] +=
+                    beam.normalize_or_zero() * (40.0 * (1.0 - progress)).min(beam.length());
+            }
+        }
+    }
+}
+
+//TODO Refactor
+fn update_secondary_player_polylines(
+    time: Res<Time>,
+    mut polylines: ResMut<Assets<Polyline>>,
+    mut polylines_query: Query<
+        (
+            &mut Handle<Polyline>,
+            &mut Visibility,
+            &Handle<PolylineMaterial>,
+            &mut PolylineTimer,
+        ),
+        With<PlayerPolylineSecondary>,
+    >,
+    mut polyline_materials: ResMut<Assets<PolylineMaterial>>,
+) {
+    for (polyline, mut visibility, material, mut timer) in polylines_query.iter_mut() {
+        timer.tick(time.delta());
+        let duration = timer.duration().as_secs_f32();
+        let elapsed = timer.elapsed().as_secs_f32();
+        let progress = (duration - elapsed) / duration;
+        if let Some(material) = polyline_materials.get_mut(material) {
+            if timer.just_finished() {
+                material.color.set_a(1.0);
+            } else {
+                // fade out laser over time
+                material.color.set_a(progress);
+            }
+        }
+        if timer.just_finished() {
+            visibility.is_visible = false;
+        }
+        if visibility.is_visible {
+            if let Some(polyline) = polylines.get_mut(&*polyline) {
+                let norm = (polyline.vertices[1] - polyline.vertices[0]).normalize();
+                //move start of laser out along normal over time
+                polyline.vertices[0] += norm * 40.0 * (1.0 - progress);
+            }
+        }
+    }
+}
+
+/// Flashes where the primary laser hits something and where the ultimate goes off
+fn spawn_player_explosions(
+    mut commands: Commands,
+    mut player_events: EventReader<PlayerEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut orb_materials: ResMut<Assets<OrbMaterial>>,
+) {
+    for player_event in player_events.iter() {
+        let (position, radius, subdivisions, color_tint, speed, scale) = match *player_event {
+            PlayerEvent::LaserImpact { position } => {
+                (position, 0.5, 1, Vec3::new(1.0, 0.2, 0.1), 5.0, 0.1)
+            }
+            PlayerEvent::Ultimate { center } => {
+                (center, 2.0, 2, Vec3::new(0.6, 0.8, 1.0), 2.0, 0.15)
+            }
+            _ => continue,
+        };
+        let orb_material = orb_materials.add(OrbMaterial {
+            material_properties: OrbProperties {
+                color_tint,
+                alpha: 1.0,
+                ..Default::default()
+            },
+            noise_texture: None,
+        });
+        commands
+            .spawn_bundle(MaterialMeshBundle {
+                mesh: meshes.add(Mesh::from(shape::Icosphere {
+                    radius,
+                    subdivisions,
+                })),
+                transform: Transform::from_translation(position),
+                material: orb_material.clone(),
+                ..Default::default()
+            })
+            .insert(RunScoped)
+            .insert(Explosion {
+                progress: 0.0,
+                speed,
+                scale,
+                handle: orb_material,
+            });
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::{Action, ActionState},
+    GameClock,
+};
+
+use super::{MovementSettings, PlayerCam, PlayerWeapon};
+
+/// Lasergun position relative to the camera, at the hip and when zoomed
+const HIP_POSITION: Vec3 = Vec3::new(0.28, -0.14, -0.12);
+const ZOOM_POSITION: Vec3 = Vec3::new(0.0, -0.1, -0.12);
+
+/// Aim down sights state for the player camera
+#[derive(Component)]
+pub struct Zoom {
+    pub zoom_fov: f32,
+    /// Seconds to go fully in or out of zoom
+    pub zoom_time: f32,
+    active: bool,
+    progress: f32,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Zoom {
+            zoom_fov: (30.0f32).to_radians(),
+            zoom_time: 0.2,
+            active: false,
+            progress: 0.0,
+        }
+    }
+}
+
+impl Zoom {
+    /// Eased zoom amount, 0.0 at the hip and 1.0 when fully zoomed
+    pub fn amount(&self) -> f32 {
+        self.progress * self.progress * (3.0 - 2.0 * self.progress)
+    }
+
+    pub fn fov(&self, base_fov: f32) -> f32 {
+        base_fov + (self.zoom_fov - base_fov) * self.amount()
+    }
+
+    /// Look sensitivity multiplier, so aim moves the same distance on screen while zoomed
+    pub fn sensitivity_scale(&self, base_fov: f32) -> f32 {
+        self.fov(base_fov) / base_fov
+    }
+}
+
+pub fn player_zoom(
+    clock: Res<GameClock>,
+    action_state: Res<ActionState>,
+    settings: Res<MovementSettings>,
+    mut cameras: Query<&mut Zoom, With<PlayerCam>>,
+    mut weapons: Query<&mut Transform, With<PlayerWeapon>>,
+) {
+    for mut zoom in cameras.iter_mut() {
+        if settings.zoom_toggle {
+            if action_state.just_pressed(Action::Zoom) {
+                zoom.active = !zoom.active;
+            }
+        } else {
+            zoom.active = action_state.pressed(Action::Zoom);
+        }
+
+        let step = clock.delta_seconds() / zoom.zoom_time;
+        zoom.progress = if zoom.active {
+            (zoom.progress + step).min(1.0)
+        } else {
+            (zoom.progress - step).max(0.0)
+        };
+
+        for mut transform in weapons.iter_mut() {
+            transform.translation = HIP_POSITION.lerp(ZOOM_POSITION, zoom.amount());
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use std::path::PathBuf;
+
+use bevy::{app::AppExit, prelude::*, window::WindowMode};
+use bevy_asset_loader::DynamicAssets;
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+
+use crate::{
+    assets::GameState,
+    bot::BotSettings,
+    enemies::{Alive, EnemiesState, Enemy},
+    modes::{difficulty::Difficulty, GameMode},
+    player::Player,
+    ui::{menu::GamePreferences, scoreboard::Scoreboard},
+    GameplaySystem,
+};
+
+use super::{replay::ReplayPlayer, start_run};
+
+/// Starts a run straight from the command line and ends the game after a set number of frames,
+/// for scripted testing and benchmarking
+pub struct LaunchPlugin;
+
+impl Plugin for LaunchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LaunchOptions>()
+            .init_resource::<StartLevel>()
+            .add_startup_system(pick_mode_and_difficulty)
+            .add_system_set(SystemSet::on_update(GameState::Menu).with_system(autostart_run))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    apply_start_level
+                        .after(GameplaySystem::Reset)
+                        .before(GameplaySystem::Player),
+                ),
+            )
+            // Keeps counting while the game over screen or the pause menu is on top of the run
+            .add_system(count_frames.after(GameplaySystem::Scoreboard));
+    }
+}
+
+/// Settings given on the command line, see [`LaunchOptions::USAGE`]
+pub struct LaunchOptions {
+    /// Skips the start menu
+    pub start: bool,
+    /// Seeds every run with this instead of a random seed
+    pub seed: Option<u64>,
+    pub mode: Option<GameMode>,
+    pub difficulty: Option<Difficulty>,
+    /// Level the enemies start at, counted like on the HUD
+    pub start_level: usize,
+    /// Used in place of the saved preferences, without overwriting them
+    pub preferences: Option<GamePreferences>,
+    pub window_mode: WindowMode,
+    pub resolution: Option<(f32, f32)>,
+    pub audio: bool,
+    /// Logs the stats of the run and exits after playing this many frames
+    pub frames: Option<u32>,
+    /// Lets the bot play this many runs, 0 keeps it playing
+    pub autoplay: Option<usize>,
+    /// A recorded run to play as soon as the game starts
+    pub replay: Option<PathBuf>,
+    /// Exits once the replay is over, logging whether it ended like the recording did
+    pub verify_replay: bool,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        LaunchOptions {
+            start: false,
+            seed: None,
+            mode: None,
+            difficulty: None,
+            start_level: 0,
+            preferences: None,
+            window_mode: WindowMode::Windowed,
+            resolution: None,
+            audio: true,
+            frames: None,
+            autoplay: None,
+            replay: None,
+            verify_replay: false,
+        }
+    }
+}
+
+impl LaunchOptions {
+    pub const USAGE: &'static str = "\
+Launch options:
+  --start                 skip the start menu
+  --seed <number>         seed every run with this
+  --mode <name>           survival, time-attack, waves or boss-rush
+  --difficulty <name>     story, normal, hard or nightmare
+  --level <number>        level the enemies start at
+  --potato                lowest graphics settings
+  --high-res              highest graphics settings
+  --fullscreen            fullscreen at the resolution below
+  --borderless            borderless fullscreen
+  --resolution <w>x<h>    window size, 1280x720 by default
+  --no-audio              play without sound
+  --frames <number>       start, play this many frames, print the stats and exit
+  --autoplay [runs]       let the bot play without a window, logging every run, for this
+                          many runs or until closed
+  --replay <file>         play a recorded run
+  --verify-replay <file>  play a recorded run without a window and exit, logging whether it
+                          ended like the recording did";
+
+    /// Reads the options out of the command line
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<LaunchOptions, String> {
+        let mut options = LaunchOptions::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--start" => options.start = true,
+                "--seed" => options.seed = Some(parse_number(&arg, value()?)?),
+                "--mode" => {
+                    let name = value()?;
+                    options.mode = Some(
+                        GameMode::all()
+                            .into_iter()
+                            .find(|mode| same_name(mode.label(), &name))
+                            .ok_or_else(|| format!("Unknown mode {}", name))?,
+                    );
+                }
+                "--difficulty" => {
+                    let name = value()?;
+                    options.difficulty = Some(
+                        Difficulty::all()
+                            .into_iter()
+                            .find(|difficulty| same_name(difficulty.label(), &name))
+                            .ok_or_else(|| format!("Unknown difficulty {}", name))?,
+                    );
+                }
+                "--level" => options.start_level = parse_number(&arg, value()?)?,
+                "--potato" => options.preferences = Some(GamePreferences::potato()),
+                // The defaults are the highest settings
+                "--high-res" => options.preferences = Some(GamePreferences::default()),
+                "--fullscreen" => options.window_mode = WindowMode::SizedFullscreen,
+                "--borderless" => options.window_mode = WindowMode::BorderlessFullscreen,
+                "--resolution" => {
+                    let size = value()?;
+                    let resolution = size.split_once('x').and_then(|(width, height)| {
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    });
+                    options.resolution = Some(
+                        resolution.ok_or_else(|| format!("Resolution {} isn't <w>x<h>", size))?,
+                    );
+                }
+                "--no-audio" => options.audio = false,
+                "--frames" => {
+                    options.frames = Some(parse_number(&arg, value()?)?);
+                    options.start = true;
+                }
+                "--autoplay" => {
+                    let runs = args.next_if(|runs| runs.parse::<usize>().is_ok());
+                    options.autoplay = Some(runs.map_or(Ok(0), |runs| parse_number(&arg, runs))?);
+                }
+                "--replay" => options.replay = Some(value()?.into()),
+                "--verify-replay" => {
+                    options.replay = Some(value()?.into());
+                    options.verify_replay = true;
+                }
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+
+    /// The bot and checking a replay only need the simulation, not a window or sound
+    pub fn headless(&self) -> bool {
+        self.autoplay.is_some() || self.verify_replay
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} takes a number, not {}", flag, value))
+}
+
+/// Matches labels like "Boss Rush" with "boss-rush", "boss_rush" or "BossRush"
+fn same_name(label: &str, name: &str) -> bool {
+    let simplify = |text: &str| {
+        text.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+    simplify(label) == simplify(name)
+}
+
+/// Level the current run started at, picked with `--level` or taken from the replay being played
+#[derive(Default)]
+pub struct StartLevel(pub usize);
+
+/// Sets the menu's mode and difficulty to the ones given on the command line
+fn pick_mode_and_difficulty(
+    options: Res<LaunchOptions>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+) {
+    if let Some(picked) = options.mode {
+        *mode = picked;
+    }
+    if let Some(picked) = options.difficulty {
+        *difficulty = picked;
+    }
+}
+
+/// Lifts the enemies and the scoreboard up to the start level. Levels only go up during a run, so
+/// this catches a run however late its reset lands.
+fn apply_start_level(
+    start_level: Res<StartLevel>,
+    mut enemies_state: ResMut<EnemiesState>,
+    mut scoreboards: Query<&mut Scoreboard>,
+) {
+    let level = start_level.0.min(enemies_state.levels.len() - 1);
+    if enemies_state.current_level < level {
+        enemies_state.current_level = level;
+    }
+    for mut scoreboard in scoreboards.iter_mut() {
+        if scoreboard.level < level {
+            scoreboard.level = level;
+        }
+    }
+}
+
+/// Starts the run for `--start`, unless the bot or a replay is already starting one
+fn autostart_run(
+    mut options: ResMut<LaunchOptions>,
+    bot_settings: Res<BotSettings>,
+    replay_player: Res<ReplayPlayer>,
+    preferences: Option<Res<GamePreferences>>,
+    mut state: ResMut<State<GameState>>,
+    mut asset_keys: Option<ResMut<DynamicAssets>>,
+) {
+    if !options.start {
+        return;
+    }
+    options.start = false;
+    if bot_settings.enabled || replay_player.is_active() {
+        return;
+    }
+    let high_res_textures = preferences.map_or(false, |preferences| preferences.high_res_textures);
+    start_run(&mut state, asset_keys.as_deref_mut(), high_res_textures);
+}
+
+/// Printed when `--frames` is up
+#[derive(Serialize)]
+struct LaunchStats<'a> {
+    frames: u32,
+    /// Real time since the run started, for benchmarking
+    seconds: f64,
+    frames_per_second: f64,
+    mode: GameMode,
+    difficulty: Difficulty,
+    score: usize,
+    scoreboard: &'a Scoreboard,
+    enemies_level: usize,
+    enemies_alive: usize,
+    player_health: f32,
+    player_shield: f32,
+    player_position: [f32; 3],
+}
+
+/// Counts the frames played for `--frames`, then prints the stats and exits. A run that ends
+/// sooner has nothing left to play, so its stats are printed at the game over.
+fn count_frames(
+    time: Res<Time>,
+    options: Res<LaunchOptions>,
+    state: Res<State<GameState>>,
+    mut played: Local<u32>,
+    mut started: Local<Option<f64>>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    enemies_state: Res<EnemiesState>,
+    scoreboards: Query<&Scoreboard>,
+    enemies: Query<(), (With<Enemy>, With<Alive>)>,
+    players: Query<(&Player, &Transform)>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let frames = match options.frames {
+        Some(frames) => frames,
+        None => return,
+    };
+    if started.is_none() && *state.current() != GameState::Playing {
+        return;
+    }
+    let started = *started.get_or_insert_with(|| time.seconds_since_startup());
+    *played += 1;
+    if *played < frames && *state.current() != GameState::GameOver {
+        return;
+    }
+    let (scoreboard, (player, player_transform)) =
+        match (scoreboards.iter().next(), players.iter().next()) {
+            (Some(scoreboard), Some(player)) => (scoreboard, player),
+            _ => return,
+        };
+    let seconds = time.seconds_since_startup() - started;
+    let stats = LaunchStats {
+        frames: *played,
+        seconds,
+        frames_per_second: *played as f64 / seconds.max(f64::EPSILON),
+        mode: *mode,
+        difficulty: *difficulty,
+        score: scoreboard.score(),
+        scoreboard,
+        enemies_level: enemies_state.current_level,
+        enemies_alive: enemies.iter().count(),
+        player_health: player.health,
+        player_shield: player.shield,
+        player_position: player_transform.translation.to_array(),
+    };
+    match ron::ser::to_string_pretty(&stats, PrettyConfig::new()) {
+        Ok(text) => println!("{}", text),
+        Err(err) => warn!("Could not write the stats: {}", err),
+    }
+    app_exit_events.send(AppExit);
+}
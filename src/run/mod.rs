@@ -0,0 +1,130 @@
+use bevy::{ecs::system::Resource, prelude::*};
+use bevy_asset_loader::DynamicAssets;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    assets::GameState,
+    input::{Action, ActionState},
+    world::level1,
+    GameplaySystem,
+};
+
+pub mod launch;
+pub mod replay;
+pub mod snapshot;
+
+/// Lifecycle of a single run, every subsystem resets its own part of it on [`ResetRun`]
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResetRun>()
+            .init_resource::<GameRng>()
+            .add_system(despawn_run_scoped.label(GameplaySystem::Reset))
+            // After the modes, so dying on the same frame wins over pausing
+            .add_system(toggle_pause.after(GameplaySystem::Modes));
+    }
+}
+
+/// Pauses or resumes the run with the menu action
+fn toggle_pause(action_state: Res<ActionState>, mut state: ResMut<State<GameState>>) {
+    if !action_state.just_pressed(Action::Menu) {
+        return;
+    }
+    // Pushing fails when another change is already queued, like dying on the same frame
+    let _ = match state.current() {
+        GameState::Playing => state.push(GameState::Paused),
+        GameState::Paused => state.pop(),
+        _ => Ok(()),
+    };
+}
+
+/// Leaves the menu for a run, loading the level first if it isn't loaded yet. Without any assets
+/// to load, like in a headless app, the run starts straight away.
+pub fn start_run(
+    state: &mut State<GameState>,
+    asset_keys: Option<&mut DynamicAssets>,
+    high_res_textures: bool,
+) {
+    if !state.inactives().is_empty() {
+        // Back from the game over screen, the level is still loaded underneath
+        state.pop().expect("Failed to change state");
+        return;
+    }
+    let next = match asset_keys {
+        Some(asset_keys) => {
+            level1::set_textures_res(asset_keys, high_res_textures);
+            GameState::Loading
+        }
+        None => GameState::Playing,
+    };
+    state.set(next).expect("Failed to change state");
+}
+
+/// Throws away everything from the current run so a new one starts as if freshly loaded
+///
+/// Systems handling it aren't tied to a state, since the pause and game over menus reset the run
+/// too.
+pub struct ResetRun;
+
+/// Entities that only live for one run, like enemies, bullets and explosions
+#[derive(Component, Default)]
+pub struct RunScoped;
+
+/// Randomness that affects gameplay, kept apart from cosmetic randomness so a run can be saved
+/// and picked up again with the same rolls
+pub struct GameRng(pub ChaCha8Rng);
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        GameRng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Another stream from the same seed, for rolls that mustn't shift the gameplay ones
+    pub fn side_stream(&self) -> ChaCha8Rng {
+        let mut rng = ChaCha8Rng::from_seed(self.0.get_seed());
+        rng.set_stream(1);
+        rng
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(ChaCha8Rng::from_entropy())
+    }
+}
+
+pub trait RunAppExt {
+    /// Inits a resource that goes back to its default whenever the run is reset
+    fn init_run_resource<R: Resource + Default>(&mut self) -> &mut Self;
+}
+
+impl RunAppExt for App {
+    fn init_run_resource<R: Resource + Default>(&mut self) -> &mut Self {
+        self.init_resource::<R>()
+            .add_system(reset_resource::<R>.label(GameplaySystem::Reset))
+    }
+}
+
+fn reset_resource<R: Resource + Default>(
+    mut reset_events: EventReader<ResetRun>,
+    mut resource: ResMut<R>,
+) {
+    if reset_events.iter().count() > 0 {
+        *resource = R::default();
+    }
+}
+
+fn despawn_run_scoped(
+    mut commands: Commands,
+    mut reset_events: EventReader<ResetRun>,
+    run_entities: Query<Entity, With<RunScoped>>,
+) {
+    if reset_events.iter().count() == 0 {
+        return;
+    }
+    for entity in run_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,579 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_asset_loader::DynamicAssets;
+use directories::ProjectDirs;
+use heron::PhysicsSteps;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::GameState,
+    input::{Action, ActionInputSystem, ActionState},
+    modes::{difficulty::Difficulty, GameMode},
+    player::{camera_effects::CameraEffectSettings, MovementSettings, Player},
+    ui::scoreboard::Scoreboard,
+    GameClock, GameClockSystem, GameplaySystem,
+};
+
+use super::{
+    launch::{LaunchOptions, StartLevel},
+    start_run, GameRng, ResetRun,
+};
+
+/// Bump when the file changes shape, older replays can then no longer be played
+const REPLAY_VERSION: u32 = 1;
+const REPLAY_DIR: &str = "replays";
+/// Older recordings are deleted once there are more than this
+const MAX_REPLAYS: usize = 20;
+
+/// Records the input of every run and plays recordings back so the run repeats exactly
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let dir = replay_dir();
+        app.insert_resource(ReplayRecorder {
+            last: dir.as_deref().and_then(latest_replay),
+            dir,
+            run_started: false,
+            recording: None,
+            finished_run: None,
+        })
+        .init_resource::<ReplayPlayer>()
+        .add_system_to_stage(CoreStage::First, play_back_step.after(GameClockSystem))
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            play_back_input.label(ActionInputSystem),
+        )
+        // Catches runs restarted while Playing stays current, like a retry from the game over
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            begin_run.label(BeginRunSystem).after(ActionInputSystem),
+        )
+        .add_system(note_reset.label(GameplaySystem::Reset))
+        .add_system_set(SystemSet::on_update(GameState::Menu).with_system(autostart_playback))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Playing)
+                .after(GameplaySystem::Reset)
+                .before(GameplaySystem::Player)
+                .with_system(begin_run),
+        )
+        .add_system_set(
+            SystemSet::on_resume(GameState::Playing)
+                .after(GameplaySystem::Reset)
+                .before(GameplaySystem::Player)
+                .with_system(begin_run),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .after(GameplaySystem::Scoreboard)
+                .with_system(record_frame)
+                .with_system(advance_playback),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(finish_run));
+    }
+}
+
+/// Label for the system that seeds a run restarted while Playing stays current
+#[derive(Debug, PartialEq, Eq, Clone, Hash, SystemLabel)]
+pub struct BeginRunSystem;
+
+/// One frame of input, a tuple to keep the file small
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct ReplayFrame(
+    /// Step in microseconds
+    u32,
+    /// A bit for every pressed [`Action`], in the order of [`Action::all`]
+    u16,
+    /// [`ActionState::move_axis`]
+    Vec2,
+    /// [`ActionState::look_delta`]
+    Vec2,
+);
+
+impl ReplayFrame {
+    fn capture(step: Duration, action_state: &ActionState) -> Self {
+        let mut actions = 0;
+        for (bit, action) in Action::all().into_iter().enumerate() {
+            // The menu isn't part of the run, and stays with whoever watches the replay
+            if action != Action::Menu && action_state.pressed(action) {
+                actions |= 1 << bit;
+            }
+        }
+        ReplayFrame(
+            step.as_micros() as u32,
+            actions,
+            action_state.move_axis,
+            action_state.look_delta,
+        )
+    }
+
+    fn step(&self) -> Duration {
+        Duration::from_micros(self.0 as u64)
+    }
+
+    fn apply(&self, action_state: &mut ActionState) {
+        action_state.release_all_except(&[Action::Menu]);
+        for (bit, action) in Action::all().into_iter().enumerate() {
+            if self.1 & (1 << bit) != 0 {
+                action_state.press(action);
+            }
+        }
+        action_state.move_axis = self.2;
+        action_state.look_delta = self.3;
+    }
+}
+
+/// How a run ended, to check a playback against
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayResult {
+    pub score: usize,
+    pub level: usize,
+    pub kills: usize,
+    pub time_survived: f32,
+}
+
+impl ReplayResult {
+    fn of(scoreboard: &Scoreboard) -> Self {
+        ReplayResult {
+            score: scoreboard.score(),
+            level: scoreboard.level,
+            kills: scoreboard.kills,
+            time_survived: scoreboard.time_survived,
+        }
+    }
+}
+
+/// A recorded run, everything that decides how it plays out besides the level itself
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    /// Level the enemies started at, missing from replays recorded before it could be picked
+    #[serde(default)]
+    pub start_level: usize,
+    /// Speed, pitch limit and zoom settings change how the same input plays out
+    movement: MovementSettings,
+    /// Recoil and shake move the aim
+    camera_effects: CameraEffectSettings,
+    frames: Vec<ReplayFrame>,
+    /// None if the run was left before it was over
+    pub result: Option<ReplayResult>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let replay = Replay::from_ron(&text).map_err(|err| err.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "recorded by another version ({}) and can't be played",
+                replay.version
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn to_ron(&self) -> ron::Result<String> {
+        ron::to_string(self)
+    }
+
+    pub fn from_ron(text: &str) -> ron::Result<Replay> {
+        ron::from_str(text)
+    }
+}
+
+/// Records the run being played and remembers the file of the last one, the default has nowhere
+/// to save and only keeps the recording in memory
+#[derive(Default)]
+pub struct ReplayRecorder {
+    dir: Option<PathBuf>,
+    /// False until the first Playing frame after a reset, when the run gets its seed
+    run_started: bool,
+    recording: Option<Replay>,
+    /// File of the run that just ended in a game over, if it was recorded
+    finished_run: Option<PathBuf>,
+    last: Option<PathBuf>,
+}
+
+impl ReplayRecorder {
+    /// The recording of the last run, finished or left
+    pub fn last(&self) -> Option<&Path> {
+        self.last.as_deref()
+    }
+
+    /// The recording of the run on the game over screen
+    pub fn finished_run(&self) -> Option<&Path> {
+        self.finished_run.as_deref()
+    }
+
+    /// Stops recording the current run, for when it no longer follows from its seed
+    pub fn abandon(&mut self) {
+        self.recording = None;
+    }
+
+    /// Writes `replay` to a new file, which becomes the last one, and deletes the oldest files
+    /// past [`MAX_REPLAYS`]
+    fn save(&mut self, replay: &Replay) -> bool {
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => return false,
+        };
+        let text = match replay.to_ron() {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("Could not save the replay: {}", err);
+                return false;
+            }
+        };
+        let mut millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        // Runs left in quick succession, like with the bot, could land on the same millisecond
+        let mut path = dir.join(format!("run-{}.ron", millis));
+        while path.exists() {
+            millis += 1;
+            path = dir.join(format!("run-{}.ron", millis));
+        }
+        match fs::create_dir_all(dir).and_then(|_| fs::write(&path, &text)) {
+            Ok(()) => {
+                info!("Saved the replay to {}", path.display());
+                prune_replays(dir);
+                self.last = Some(path);
+                true
+            }
+            Err(err) => {
+                warn!("Could not save the replay to {}: {}", path.display(), err);
+                false
+            }
+        }
+    }
+}
+
+/// Feeds a recording back into the game in place of the player's input
+#[derive(Default)]
+pub struct ReplayPlayer {
+    replay: Option<Replay>,
+    /// Frame to play next, None until the run starts
+    frame: Option<usize>,
+    /// The player's own settings, put back once the playback is over
+    own_settings: Option<(MovementSettings, CameraEffectSettings)>,
+    /// Starts the run from the menu without waiting for a button
+    autostart: bool,
+    /// Exits once the playback is over, for checking replays from the command line
+    exit_when_done: bool,
+    /// Whether the playback ended the way the recording did, once it is over
+    pub verified: Option<bool>,
+}
+
+impl ReplayPlayer {
+    /// Plays `replay` as soon as the game starts, then exits if `exit_when_done`
+    pub fn autoplay(replay: Replay, exit_when_done: bool) -> Self {
+        ReplayPlayer {
+            replay: Some(replay),
+            autostart: true,
+            exit_when_done,
+            ..Default::default()
+        }
+    }
+
+    /// Loads a replay to play once the run starts, returns false if it can't be played
+    pub fn watch(&mut self, path: &Path) -> bool {
+        match Replay::load(path) {
+            Ok(replay) => {
+                *self = ReplayPlayer {
+                    replay: Some(replay),
+                    ..Default::default()
+                };
+                true
+            }
+            Err(err) => {
+                warn!("Could not play {}: {}", path.display(), err);
+                false
+            }
+        }
+    }
+
+    /// True from picking a replay until the run it plays is reset
+    pub fn is_active(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Input for the current frame while the playback runs
+    fn current(&self) -> Option<&ReplayFrame> {
+        self.replay.as_ref()?.frames.get(self.frame?)
+    }
+
+    /// Compares the end of the playback with the recording and gives the settings back
+    fn finish(
+        &mut self,
+        result: Option<ReplayResult>,
+        movement: &mut MovementSettings,
+        camera_effects: &mut CameraEffectSettings,
+    ) {
+        let (replay, frame) = match (&self.replay, self.frame) {
+            (Some(replay), Some(frame)) if self.verified.is_none() => (replay, frame),
+            _ => return,
+        };
+        let verified = frame >= replay.frames.len() && result == replay.result;
+        if verified {
+            info!("Replay finished like the recording: {:?}", result);
+        } else {
+            warn!(
+                "Replay diverged after {} of {} frames: recorded {:?}, played {:?}",
+                frame.min(replay.frames.len()),
+                replay.frames.len(),
+                replay.result,
+                result
+            );
+        }
+        self.verified = Some(verified);
+        if let Some((own_movement, own_camera_effects)) = self.own_settings.take() {
+            *movement = own_movement;
+            *camera_effects = own_camera_effects;
+        }
+    }
+
+    /// Whether the settings are the recording's rather than the player's
+    pub fn borrows_settings(&self) -> bool {
+        self.own_settings.is_some()
+    }
+}
+
+fn replay_dir() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "confluence-of-futility").map(|dirs| dirs.data_dir().join(REPLAY_DIR))
+}
+
+/// Replays are named by the time they were saved, so sorted by name they go from oldest to newest
+fn replay_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .map_or(false, |extension| extension == "ron")
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+fn latest_replay(dir: &Path) -> Option<PathBuf> {
+    replay_files(dir).pop()
+}
+
+fn prune_replays(dir: &Path) {
+    let files = replay_files(dir);
+    let extra = files.len().saturating_sub(MAX_REPLAYS);
+    for path in &files[..extra] {
+        if let Err(err) = fs::remove_file(path) {
+            warn!(
+                "Could not delete the old replay {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Steps gameplay by the recorded step rather than the time the frame took while a replay plays
+fn play_back_step(
+    replay_player: Res<ReplayPlayer>,
+    mut clock: ResMut<GameClock>,
+    mut physics_steps: ResMut<PhysicsSteps>,
+) {
+    if let Some(frame) = replay_player.current() {
+        clock.delta = frame.step();
+        *physics_steps = PhysicsSteps::every_frame(frame.step());
+    }
+}
+
+/// Replaces whatever the devices put into [`ActionState`] with the recorded input
+pub fn play_back_input(replay_player: Res<ReplayPlayer>, mut action_state: ResMut<ActionState>) {
+    if let Some(frame) = replay_player.current() {
+        frame.apply(&mut action_state);
+    }
+}
+
+/// Seeds a new run and starts recording it, or starts the playback that was picked
+fn begin_run(
+    state: Res<State<GameState>>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut rng: ResMut<GameRng>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut movement: ResMut<MovementSettings>,
+    mut camera_effects: ResMut<CameraEffectSettings>,
+    mut clock: ResMut<GameClock>,
+    mut physics_steps: ResMut<PhysicsSteps>,
+    mut players: Query<&mut Player>,
+    launch: Res<LaunchOptions>,
+    mut start_level: ResMut<StartLevel>,
+) {
+    if recorder.run_started || *state.current() != GameState::Playing {
+        return;
+    }
+    recorder.run_started = true;
+    recorder.finished_run = None;
+    let playback = match (&replay_player.replay, replay_player.frame) {
+        (Some(replay), None) => Some((
+            replay.seed,
+            replay.mode,
+            replay.difficulty,
+            replay.start_level,
+            replay.movement.clone(),
+            replay.camera_effects.clone(),
+        )),
+        _ => None,
+    };
+    let (seed, level) = match playback {
+        Some((
+            seed,
+            replay_mode,
+            replay_difficulty,
+            start_level,
+            replay_movement,
+            replay_camera_effects,
+        )) => {
+            replay_player.own_settings = Some((
+                std::mem::replace(&mut *movement, replay_movement),
+                std::mem::replace(&mut *camera_effects, replay_camera_effects),
+            ));
+            *mode = replay_mode;
+            *difficulty = replay_difficulty;
+            replay_player.frame = Some(0);
+            // The clock already stepped this frame, before the playback began
+            if let Some(frame) = replay_player.current() {
+                clock.delta = frame.step();
+                *physics_steps = PhysicsSteps::every_frame(frame.step());
+            }
+            (seed, start_level)
+        }
+        None => {
+            let seed = launch.seed.unwrap_or_else(rand::random);
+            let start_level = launch.start_level;
+            recorder.recording = Some(Replay {
+                version: REPLAY_VERSION,
+                seed,
+                mode: *mode,
+                difficulty: *difficulty,
+                start_level,
+                movement: movement.clone(),
+                camera_effects: camera_effects.clone(),
+                frames: Vec::new(),
+                result: None,
+            });
+            (seed, start_level)
+        }
+    };
+    *rng = GameRng::seeded(seed);
+    start_level.0 = level;
+    // A difficulty picked in the same frame as the run started hasn't reached the player yet
+    for mut player in players.iter_mut() {
+        player.set_difficulty(*difficulty);
+        player.reset();
+    }
+}
+
+fn record_frame(
+    clock: Res<GameClock>,
+    action_state: Res<ActionState>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if let Some(replay) = recorder.recording.as_mut() {
+        replay
+            .frames
+            .push(ReplayFrame::capture(clock.delta(), &action_state));
+    }
+}
+
+fn advance_playback(
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut movement: ResMut<MovementSettings>,
+    mut camera_effects: ResMut<CameraEffectSettings>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    let frames = match (&replay_player.replay, replay_player.frame) {
+        (Some(replay), Some(_)) => replay.frames.len(),
+        _ => return,
+    };
+    let frame = replay_player.frame.map_or(0, |frame| frame + 1);
+    replay_player.frame = Some(frame);
+    // A recording that ended in a game over has its last frame played just before it, so still
+    // running past the end means the run was left early or the playback diverged
+    if frame == frames + 1 {
+        replay_player.finish(None, &mut movement, &mut camera_effects);
+        if replay_player.exit_when_done {
+            app_exit_events.send(AppExit);
+        }
+    }
+}
+
+/// Saves the recording of a finished run and checks a playback against its recording
+fn finish_run(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut movement: ResMut<MovementSettings>,
+    mut camera_effects: ResMut<CameraEffectSettings>,
+    mut app_exit_events: EventWriter<AppExit>,
+    scoreboards: Query<&Scoreboard>,
+) {
+    let result = scoreboards.iter().next().map(ReplayResult::of);
+    if let Some(mut replay) = recorder.recording.take() {
+        replay.result = result;
+        if recorder.save(&replay) {
+            recorder.finished_run = recorder.last.clone();
+        }
+    }
+    if replay_player.is_active() {
+        replay_player.finish(result, &mut movement, &mut camera_effects);
+        if replay_player.exit_when_done {
+            app_exit_events.send(AppExit);
+        }
+    }
+}
+
+/// Saves a run that was left before it was over and ends any playback
+fn note_reset(
+    mut reset_events: EventReader<ResetRun>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut movement: ResMut<MovementSettings>,
+    mut camera_effects: ResMut<CameraEffectSettings>,
+) {
+    if reset_events.iter().count() == 0 {
+        return;
+    }
+    if let Some(replay) = recorder.recording.take() {
+        if !replay.frames.is_empty() {
+            recorder.save(&replay);
+        }
+    }
+    recorder.run_started = false;
+    if replay_player.frame.is_some() {
+        replay_player.finish(None, &mut movement, &mut camera_effects);
+        *replay_player = ReplayPlayer::default();
+    }
+}
+
+/// Starts the run for a replay given on the command line
+fn autostart_playback(
+    mut replay_player: ResMut<ReplayPlayer>,
+    mut state: ResMut<State<GameState>>,
+    mut asset_keys: Option<ResMut<DynamicAssets>>,
+) {
+    if !replay_player.autostart {
+        return;
+    }
+    replay_player.autostart = false;
+    start_run(&mut state, asset_keys.as_deref_mut(), false);
+}
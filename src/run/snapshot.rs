@@ -0,0 +1,381 @@
+use std::{cmp::Ordering, fs, path::PathBuf, time::Duration};
+
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use heron::rapier_plugin::{convert::IntoRapier, rapier3d::prelude::RigidBodySet, RigidBodyHandle};
+use rand_chacha::ChaCha8Rng;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemies::{
+        laserie::LaserieEnemy, spawn_enemy_in_world, Alive, EnemiesState, Enemy, EnemyKind,
+        EnemySpawnTimer,
+    },
+    modes::{difficulty::Difficulty, GameMode, ModeState},
+    player::{ultimate::Ultimate, InputState, Player, PlayerCam},
+    ui::scoreboard::Scoreboard,
+    GameClock, GameplaySystem,
+};
+
+use super::{replay::ReplayRecorder, GameRng, RunScoped};
+
+/// Bump when the snapshot changes shape, older saves are then ignored
+const SNAPSHOT_VERSION: u32 = 3;
+const SNAPSHOT_FILE: &str = "run.ron";
+
+/// Saves the run from the pause menu and picks it up again from the start menu
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveRun>()
+            .insert_resource(SavedRun {
+                path: snapshot_path(),
+                pending: None,
+            })
+            .add_system(save_run.exclusive_system())
+            .add_system(resume_saved_run.exclusive_system())
+            .add_system(apply_restored_velocity.after(GameplaySystem::Enemies));
+    }
+}
+
+/// Writes the current run to disk
+pub struct SaveRun;
+
+/// Where the run is saved and the snapshot waiting to be restored once the player is spawned
+pub struct SavedRun {
+    path: Option<PathBuf>,
+    pending: Option<RunSnapshot>,
+}
+
+impl SavedRun {
+    pub fn exists(&self) -> bool {
+        self.path.as_ref().map_or(false, |path| path.exists())
+    }
+
+    /// Reads the saved run so it is restored as soon as a run starts, returns false if there is
+    /// nothing usable to resume
+    pub fn resume(&mut self) -> bool {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return false,
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("Could not read {}: {}", path.display(), err);
+                return false;
+            }
+        };
+        match RunSnapshot::from_ron(&text) {
+            Ok(snapshot) if snapshot.version == SNAPSHOT_VERSION => {
+                self.pending = Some(snapshot);
+                true
+            }
+            Ok(snapshot) => {
+                warn!(
+                    "Saved run is from another version ({}), it can't be resumed",
+                    snapshot.version
+                );
+                false
+            }
+            Err(err) => {
+                warn!("Could not read saved run {}: {}", path.display(), err);
+                false
+            }
+        }
+    }
+}
+
+/// Velocity for an enemy whose rigid body hasn't been created yet
+#[derive(Component)]
+struct RestoredVelocity {
+    linear: [f32; 3],
+    angular: [f32; 3],
+}
+
+/// Everything needed to pick a run up where it was left. Bullets in flight, explosions and
+/// enemies that are already dying are not kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    pub version: u32,
+    /// Seconds on the [`GameClock`]
+    pub clock: f32,
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub mode_state: ModeState,
+    pub enemies_state: EnemiesState,
+    pub spawn_timer: SpawnTimerSnapshot,
+    pub enemies: Vec<EnemySnapshot>,
+    pub player: PlayerSnapshot,
+    pub scoreboard: Scoreboard,
+    pub rng: ChaCha8Rng,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnTimerSnapshot {
+    /// False until the player first fires
+    pub running: bool,
+    pub interval: Duration,
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct EnemySnapshot {
+    pub kind: EnemyKind,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub linear_velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    pub health: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub translation: [f32; 3],
+    pub health: f32,
+    pub shield: f32,
+    pub ultimate_charge: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+impl RunSnapshot {
+    /// Reads the run out of a world set up for playing
+    pub fn capture(world: &mut World) -> RunSnapshot {
+        let mut enemies_query = world.query_filtered::<(
+            &Transform,
+            &Enemy,
+            Option<&LaserieEnemy>,
+            Option<&RigidBodyHandle>,
+            Option<&RestoredVelocity>,
+        ), With<Alive>>();
+        let rigid_bodies = world.get_resource::<RigidBodySet>();
+        let mut enemies = enemies_query
+            .iter(world)
+            .map(|(transform, enemy, laserie, handle, restored)| {
+                let body = handle
+                    .zip(rigid_bodies)
+                    .and_then(|(handle, bodies)| bodies.get(handle.into_rapier()));
+                let (linear_velocity, angular_velocity) = match (body, restored) {
+                    (Some(body), _) => (
+                        [body.linvel().x, body.linvel().y, body.linvel().z],
+                        [body.angvel().x, body.angvel().y, body.angvel().z],
+                    ),
+                    (None, Some(restored)) => (restored.linear, restored.angular),
+                    (None, None) => ([0.0; 3], [0.0; 3]),
+                };
+                EnemySnapshot {
+                    kind: EnemyKind::of(enemy, laserie.is_none()),
+                    translation: transform.translation.to_array(),
+                    rotation: transform.rotation.to_array(),
+                    linear_velocity,
+                    angular_velocity,
+                    health: enemy.health,
+                }
+            })
+            .collect::<Vec<_>>();
+        // Sorted so the same world always gives the same snapshot, whatever order entities are in
+        enemies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let input_state = world.resource::<InputState>();
+        let (pitch, yaw) = (input_state.pitch, input_state.yaw);
+        let player = world
+            .query::<(&Transform, &Player, Option<&Ultimate>)>()
+            .iter(world)
+            .next()
+            .map(|(transform, player, ultimate)| PlayerSnapshot {
+                translation: transform.translation.to_array(),
+                health: player.health,
+                shield: player.shield,
+                ultimate_charge: ultimate.map_or(0.0, |ultimate| ultimate.charge),
+                pitch,
+                yaw,
+            })
+            .unwrap_or_default();
+        let scoreboard = world
+            .query::<&Scoreboard>()
+            .iter(world)
+            .next()
+            .cloned()
+            .unwrap_or_default();
+
+        let timer = &world.resource::<EnemySpawnTimer>().0;
+        RunSnapshot {
+            version: SNAPSHOT_VERSION,
+            clock: world.resource::<GameClock>().seconds(),
+            mode: *world.resource::<GameMode>(),
+            difficulty: *world.resource::<Difficulty>(),
+            mode_state: world.resource::<ModeState>().clone(),
+            enemies_state: world.resource::<EnemiesState>().clone(),
+            spawn_timer: SpawnTimerSnapshot {
+                running: !timer.paused(),
+                interval: timer.duration(),
+                elapsed: timer.elapsed(),
+            },
+            enemies,
+            player,
+            scoreboard,
+            rng: world.resource::<GameRng>().0.clone(),
+        }
+    }
+
+    /// Replaces the current run with this one, the level and player stay in place
+    pub fn restore(&self, world: &mut World) {
+        let run_entities = world
+            .query_filtered::<Entity, With<RunScoped>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+        for entity in run_entities {
+            // Children of other run scoped entities are already gone
+            if let Some(entity) = world.get_entity_mut(entity) {
+                entity.despawn_recursive();
+            }
+        }
+
+        // Keeps the step of the current frame
+        world.resource_mut::<GameClock>().elapsed = self.clock;
+        world.insert_resource(self.mode);
+        world.insert_resource(self.difficulty);
+        world.insert_resource(self.mode_state.clone());
+        world.insert_resource(self.enemies_state.clone());
+        world.insert_resource(GameRng(self.rng.clone()));
+        world.insert_resource(InputState {
+            pitch: self.player.pitch,
+            yaw: self.player.yaw,
+        });
+        let mut timer = Timer::new(self.spawn_timer.interval, true);
+        timer.set_elapsed(self.spawn_timer.elapsed);
+        if !self.spawn_timer.running {
+            timer.pause();
+        }
+        world.insert_resource(EnemySpawnTimer(timer));
+
+        for enemy in &self.enemies {
+            let transform = Transform::from_translation(Vec3::from(enemy.translation))
+                .with_rotation(Quat::from_array(enemy.rotation));
+            let entity = spawn_enemy_in_world(world, enemy.kind, transform);
+            let mut entity = world.entity_mut(entity);
+            if let Some(mut spawned) = entity.get_mut::<Enemy>() {
+                spawned.health = enemy.health;
+            }
+            entity.insert(RestoredVelocity {
+                linear: enemy.linear_velocity,
+                angular: enemy.angular_velocity,
+            });
+        }
+
+        let translation = Vec3::from(self.player.translation);
+        let mut handles = Vec::new();
+        for (mut transform, mut player, ultimate, handle) in world
+            .query::<(
+                &mut Transform,
+                &mut Player,
+                Option<&mut Ultimate>,
+                Option<&RigidBodyHandle>,
+            )>()
+            .iter_mut(world)
+        {
+            transform.translation = translation;
+            transform.rotation = Quat::from_axis_angle(Vec3::Y, self.player.yaw);
+            player.set_difficulty(self.difficulty);
+            player.health = self.player.health;
+            player.shield = self.player.shield;
+            if let Some(mut ultimate) = ultimate {
+                ultimate.charge = self.player.ultimate_charge;
+            }
+            handles.extend(handle.map(|handle| handle.into_rapier()));
+        }
+        if let Some(mut rigid_bodies) = world.get_resource_mut::<RigidBodySet>() {
+            for handle in handles {
+                if let Some(body) = rigid_bodies.get_mut(handle) {
+                    body.set_translation(translation.to_array().into(), true);
+                    body.set_linvel([0.0, 0.0, 0.0].into(), true);
+                }
+            }
+        }
+        for mut transform in world
+            .query_filtered::<&mut Transform, With<PlayerCam>>()
+            .iter_mut(world)
+        {
+            transform.rotation = Quat::from_axis_angle(Vec3::X, self.player.pitch);
+        }
+
+        for mut scoreboard in world.query::<&mut Scoreboard>().iter_mut(world) {
+            *scoreboard = self.scoreboard.clone();
+        }
+    }
+
+    pub fn to_ron(&self) -> ron::Result<String> {
+        ron::ser::to_string_pretty(self, PrettyConfig::new())
+    }
+
+    pub fn from_ron(text: &str) -> ron::Result<RunSnapshot> {
+        ron::from_str(text)
+    }
+}
+
+fn snapshot_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "confluence-of-futility")
+        .map(|dirs| dirs.data_dir().join(SNAPSHOT_FILE))
+}
+
+fn save_run(world: &mut World) {
+    if world.resource_mut::<Events<SaveRun>>().drain().count() == 0 {
+        return;
+    }
+    let path = match &world.resource::<SavedRun>().path {
+        Some(path) => path.clone(),
+        None => {
+            warn!("No data directory found, the run can't be saved");
+            return;
+        }
+    };
+    let text = match RunSnapshot::capture(world).to_ron() {
+        Ok(text) => text,
+        Err(err) => {
+            warn!("Could not save the run: {}", err);
+            return;
+        }
+    };
+    let written = match path.parent() {
+        Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(&path, &text)),
+        None => fs::write(&path, &text),
+    };
+    match written {
+        Ok(()) => info!("Saved the run to {}", path.display()),
+        Err(err) => warn!("Could not save the run to {}: {}", path.display(), err),
+    }
+}
+
+/// Restores a resumed run once the player and scoreboard it goes into exist
+fn resume_saved_run(world: &mut World) {
+    if world.resource::<SavedRun>().pending.is_none() {
+        return;
+    }
+    let has_player = world.query::<&Player>().iter(world).next().is_some();
+    let has_scoreboard = world.query::<&Scoreboard>().iter(world).next().is_some();
+    if !has_player || !has_scoreboard {
+        return;
+    }
+    if let Some(snapshot) = world.resource_mut::<SavedRun>().pending.take() {
+        snapshot.restore(world);
+        // The run no longer follows from the seed it started with
+        world.resource_mut::<ReplayRecorder>().abandon();
+    }
+}
+
+fn apply_restored_velocity(
+    mut commands: Commands,
+    mut rigid_bodies: ResMut<RigidBodySet>,
+    enemies: Query<(Entity, &RigidBodyHandle, &RestoredVelocity), Added<RigidBodyHandle>>,
+) {
+    for (entity, handle, velocity) in enemies.iter() {
+        if let Some(body) = rigid_bodies.get_mut(handle.into_rapier()) {
+            body.set_linvel(velocity.linear.into(), true);
+            body.set_angvel(velocity.angular.into(), true);
+        }
+        commands.entity(entity).remove::<RestoredVelocity>();
+    }
+}
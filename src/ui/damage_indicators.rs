@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{Color32, Id, LayerId, Order, Pos2, Shape, Stroke},
+    EguiContext,
+};
+
+use crate::{
+    assets::GameState,
+    player::{HitKind, PlayerCam, PlayerEvent},
+    run::RunAppExt,
+};
+
+/// Distance from the crosshair to the arcs, in points
+const INDICATOR_RADIUS: f32 = 90.0;
+/// Hits from sources closer together than this refresh the same indicator
+const MERGE_DISTANCE: f32 = 2.0;
+
+pub struct DamageIndicatorPlugin;
+
+impl Plugin for DamageIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_run_resource::<DamageIndicators>().add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(add_indicators)
+                .with_system(draw_indicators.after(add_indicators)),
+        );
+    }
+}
+
+struct DamageIndicator {
+    kind: HitKind,
+    source: Vec3,
+    direction: Vec3,
+    age: f32,
+}
+
+#[derive(Default)]
+struct DamageIndicators(Vec<DamageIndicator>);
+
+struct IndicatorStyle {
+    lifetime: f32,
+    /// Half of the arc length in radians
+    half_angle: f32,
+    width: f32,
+    color: [u8; 3],
+}
+
+fn indicator_style(kind: HitKind) -> IndicatorStyle {
+    match kind {
+        // Lasers hit every frame while they are on target, so keep the arc short lived
+        HitKind::Laser => IndicatorStyle {
+            lifetime: 0.3,
+            half_angle: 0.15,
+            width: 3.0,
+            color: [255, 40, 40],
+        },
+        HitKind::Projectile => IndicatorStyle {
+            lifetime: 1.2,
+            half_angle: 0.25,
+            width: 6.0,
+            color: [255, 150, 30],
+        },
+        HitKind::Splash => IndicatorStyle {
+            lifetime: 1.2,
+            half_angle: 0.5,
+            width: 4.0,
+            color: [255, 210, 60],
+        },
+    }
+}
+
+fn add_indicators(
+    time: Res<Time>,
+    mut player_events: EventReader<PlayerEvent>,
+    mut indicators: ResMut<DamageIndicators>,
+) {
+    for indicator in indicators.0.iter_mut() {
+        indicator.age += time.delta_seconds();
+    }
+    indicators
+        .0
+        .retain(|indicator| indicator.age < indicator_style(indicator.kind).lifetime);
+
+    for player_event in player_events.iter() {
+        if let PlayerEvent::Hit {
+            kind,
+            source,
+            direction,
+        } = *player_event
+        {
+            if let Some(indicator) = indicators.0.iter_mut().find(|indicator| {
+                indicator.kind == kind && indicator.source.distance(source) < MERGE_DISTANCE
+            }) {
+                indicator.source = source;
+                indicator.direction = direction;
+                indicator.age = 0.0;
+            } else {
+                indicators.0.push(DamageIndicator {
+                    kind,
+                    source,
+                    direction,
+                    age: 0.0,
+                });
+            }
+        }
+    }
+}
+
+/// Draws an arc around the crosshair for each recent hit, pointing toward the attacker
+fn draw_indicators(
+    mut egui_context: ResMut<EguiContext>,
+    indicators: Res<DamageIndicators>,
+    player_cams: Query<&GlobalTransform, With<PlayerCam>>,
+) {
+    if indicators.0.is_empty() {
+        return;
+    }
+    let cam_transform = match player_cams.iter().next() {
+        Some(cam_transform) => cam_transform,
+        None => return,
+    };
+    let ctx = egui_context.ctx_mut();
+    let center = ctx.input().screen_rect().center();
+    let painter = ctx.layer_painter(LayerId::new(
+        Order::Foreground,
+        Id::new("damage_indicators"),
+    ));
+
+    for indicator in indicators.0.iter() {
+        let mut to_source = indicator.source - cam_transform.translation;
+        if to_source.length_squared() < 1.0 {
+            // Too close to tell where the source is, use where the hit came from instead
+            to_source = -indicator.direction;
+        }
+        // Angle in the view plane, 0 is straight ahead and positive is to the right
+        let local = cam_transform.rotation.inverse() * to_source;
+        let angle = local.x.atan2(-local.z);
+
+        let style = indicator_style(indicator.kind);
+        let alpha = 1.0 - indicator.age / style.lifetime;
+        let [r, g, b] = style.color;
+        let color = Color32::from_rgba_unmultiplied(r, g, b, (alpha * 220.0) as u8);
+
+        let segments = 12;
+        let points = (0..=segments)
+            .map(|i| {
+                let a =
+                    angle - style.half_angle + style.half_angle * 2.0 * i as f32 / segments as f32;
+                Pos2::new(
+                    center.x + INDICATOR_RADIUS * a.sin(),
+                    center.y - INDICATOR_RADIUS * a.cos(),
+                )
+            })
+            .collect::<Vec<_>>();
+        painter.add(Shape::line(points, Stroke::new(style.width, color)));
+
+        // Point the arc at the attacker with a small tick on its outside edge
+        let tip = INDICATOR_RADIUS + style.width * 2.0;
+        painter.add(Shape::line_segment(
+            [
+                Pos2::new(
+                    center.x + (INDICATOR_RADIUS + 1.0) * angle.sin(),
+                    center.y - (INDICATOR_RADIUS + 1.0) * angle.cos(),
+                ),
+                Pos2::new(center.x + tip * angle.sin(), center.y - tip * angle.cos()),
+            ],
+            Stroke::new(style.width, color),
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Align2},
+    EguiContext,
+};
+
+use crate::{
+    assets::GameState,
+    bot::BotSettings,
+    enemies::EnemyKind,
+    modes::{difficulty::Difficulty, GameMode, ModeState, RunOutcome},
+    player::WeaponKind,
+    run::{
+        replay::{ReplayPlayer, ReplayRecorder},
+        ResetRun,
+    },
+};
+
+use super::{
+    high_scores::{HighScore, HighScores},
+    menu::MenuNavigation,
+    scoreboard::{format_accuracy, Scoreboard},
+};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(release_cursor))
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(game_over_ui));
+    }
+}
+
+/// Frees the mouse for the game over menu
+fn release_cursor(mut windows: ResMut<Windows>) {
+    let window = windows.get_primary_mut().unwrap();
+    window.set_cursor_lock_mode(false);
+    window.set_cursor_visibility(true);
+}
+
+fn game_over_ui(
+    mut state: ResMut<State<GameState>>,
+    mut windows: ResMut<Windows>,
+    mut egui_context: ResMut<EguiContext>,
+    mut navigation: ResMut<MenuNavigation>,
+    mut reset_events: EventWriter<ResetRun>,
+    mut high_scores: ResMut<HighScores>,
+    bot_settings: Res<BotSettings>,
+    replay_recorder: Res<ReplayRecorder>,
+    replay_player: Res<ReplayPlayer>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    mode_state: Res<ModeState>,
+    scoreboards: Query<&Scoreboard>,
+) {
+    let window = windows.get_primary_mut().unwrap();
+    let scoreboard = match scoreboards.iter().next() {
+        Some(scoreboard) => scoreboard,
+        None => return,
+    };
+    // Every frame so kills and bonuses that land after the run ended still count
+    // Bot runs are for balance testing and watched replays were already counted
+    if let (Some(outcome), false) = (
+        mode_state.outcome,
+        bot_settings.enabled || replay_player.is_active(),
+    ) {
+        let mut entry = HighScore::new(scoreboard, *mode, *difficulty, outcome);
+        entry.replay = replay_recorder
+            .finished_run()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned());
+        high_scores.submit(entry);
+    }
+    let (title, message) = match mode_state.outcome {
+        Some(RunOutcome::Won) => ("VICTORY", "Against all odds, you have achieved victory."),
+        Some(RunOutcome::TimeUp) => ("TIME UP", "Your time for futility has run out."),
+        _ => ("GAME OVER", "You have failed to achieve victory."),
+    };
+    navigation.clamp(2);
+    egui::Window::new(title)
+        .resizable(false)
+        .collapsible(false)
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(message);
+            ui.separator();
+            egui::Grid::new("run_summary").show(ui, |ui| {
+                ui.label("Mode");
+                ui.label(mode.label());
+                ui.end_row();
+                ui.label("Difficulty");
+                ui.label(difficulty.label());
+                ui.end_row();
+                ui.label("Score");
+                ui.label(scoreboard.score().to_string());
+                ui.end_row();
+                ui.label("Level reached");
+                ui.label(scoreboard.level.to_string());
+                ui.end_row();
+                ui.label("Accuracy");
+                ui.label(format_accuracy(scoreboard.accuracy()));
+                ui.end_row();
+                for weapon in WeaponKind::all() {
+                    let stats = scoreboard.weapon_stats(weapon);
+                    ui.label(format!("{} accuracy", weapon.label()));
+                    ui.label(format!(
+                        "{} ({} hit, {} wall, {} missed)",
+                        format_accuracy(stats.accuracy()),
+                        stats.hits,
+                        stats.world_hits,
+                        stats.misses
+                    ));
+                    ui.end_row();
+                }
+                ui.label("Time survived");
+                let seconds = scoreboard.time_survived as u32;
+                ui.label(format!("{}:{:02}", seconds / 60, seconds % 60));
+                ui.end_row();
+                for kind in EnemyKind::all() {
+                    ui.label(format!("{} kills", kind.label()));
+                    ui.label(
+                        scoreboard
+                            .kills_by_kind
+                            .get(&kind)
+                            .copied()
+                            .unwrap_or(0)
+                            .to_string(),
+                    );
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            ui.collapsing("High scores", |ui| {
+                high_scores.build_ui(ui, *mode, *difficulty);
+            });
+            ui.separator();
+            ui.vertical_centered_justified(|ui| {
+                if navigation.button(ui, 0, "Retry") {
+                    high_scores.finish_run();
+                    reset_events.send(ResetRun);
+                    state.pop().expect("Failed to change state");
+                    window.set_cursor_lock_mode(true);
+                    window.set_cursor_visibility(false);
+                }
+                if navigation.button(ui, 1, "Main Menu") {
+                    high_scores.finish_run();
+                    reset_events.send(ResetRun);
+                    // Replaces GameOver, Playing stays paused underneath until START is pressed
+                    state.set(GameState::Menu).expect("Failed to change state");
+                }
+            });
+        });
+}
@@ -0,0 +1,225 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_egui::egui;
+use directories::ProjectDirs;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::modes::{difficulty::Difficulty, GameMode, RunOutcome};
+
+use super::scoreboard::{format_accuracy, Scoreboard};
+
+/// Bump when the file changes shape, older scores are then started over
+const HIGH_SCORES_VERSION: u32 = 1;
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+/// Scores kept for every mode and difficulty
+const KEPT_PER_TABLE: usize = 10;
+
+/// Best runs for every mode and difficulty, kept between sessions
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores {
+            path: high_scores_path(),
+            ..Default::default()
+        })
+        .add_startup_system(load_high_scores);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: usize,
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub outcome: RunOutcome,
+    pub level: usize,
+    pub kills: usize,
+    pub accuracy: Option<f32>,
+    pub time_survived: f32,
+    /// File name of the run's replay in the replays folder, to check the score with
+    #[serde(default)]
+    pub replay: Option<String>,
+}
+
+impl HighScore {
+    pub fn new(
+        scoreboard: &Scoreboard,
+        mode: GameMode,
+        difficulty: Difficulty,
+        outcome: RunOutcome,
+    ) -> Self {
+        HighScore {
+            score: scoreboard.score(),
+            mode,
+            difficulty,
+            outcome,
+            level: scoreboard.level,
+            kills: scoreboard.kills,
+            accuracy: scoreboard.accuracy(),
+            time_survived: scoreboard.time_survived,
+            replay: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HighScoresFile {
+    version: u32,
+    entries: Vec<HighScore>,
+}
+
+#[derive(Default)]
+pub struct HighScores {
+    path: Option<PathBuf>,
+    /// Best first
+    entries: Vec<HighScore>,
+    /// The entry of the run on the game over screen, it keeps up with late scoreboard updates
+    current: Option<usize>,
+}
+
+impl HighScores {
+    /// Best scores first for one mode and difficulty, with the position in [`HighScores::entries`]
+    fn table(
+        &self,
+        mode: GameMode,
+        difficulty: Difficulty,
+    ) -> impl Iterator<Item = (usize, &HighScore)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(move |(_, entry)| entry.mode == mode && entry.difficulty == difficulty)
+            .take(KEPT_PER_TABLE)
+    }
+
+    /// Adds or updates the run being shown on the game over screen, saving if anything changed
+    pub fn submit(&mut self, entry: HighScore) {
+        if let Some(current) = self.current {
+            if self.entries[current] == entry {
+                return;
+            }
+            self.entries.remove(current);
+        }
+        let index = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+        self.current = Some(index);
+        self.trim();
+        self.save();
+    }
+
+    /// Call when leaving the game over screen so the next run gets an entry of its own
+    pub fn finish_run(&mut self) {
+        self.current = None;
+    }
+
+    /// Drops scores that fell off their table, the current run always stays
+    fn trim(&mut self) {
+        let mut kept = Vec::new();
+        let mut current = None;
+        for (index, entry) in self.entries.drain(..).enumerate() {
+            let rank = kept
+                .iter()
+                .filter(|other: &&HighScore| {
+                    other.mode == entry.mode && other.difficulty == entry.difficulty
+                })
+                .count();
+            if Some(index) == self.current {
+                current = Some(kept.len());
+            } else if rank >= KEPT_PER_TABLE {
+                continue;
+            }
+            kept.push(entry);
+        }
+        self.entries = kept;
+        self.current = current;
+    }
+
+    fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let file = HighScoresFile {
+            version: HIGH_SCORES_VERSION,
+            entries: self.entries.clone(),
+        };
+        let text = match ron::ser::to_string_pretty(&file, PrettyConfig::new()) {
+            Ok(text) => text,
+            Err(err) => {
+                warn!("Could not save high scores: {}", err);
+                return;
+            }
+        };
+        let written = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(path, &text)),
+            None => fs::write(path, &text),
+        };
+        if let Err(err) = written {
+            warn!("Could not save high scores to {}: {}", path.display(), err);
+        }
+    }
+
+    /// Lists the best runs for one mode and difficulty, highlighting the current one
+    pub fn build_ui(&self, ui: &mut egui::Ui, mode: GameMode, difficulty: Difficulty) {
+        ui.label(format!("{} on {}", mode.label(), difficulty.label()));
+        let mut table = self.table(mode, difficulty).peekable();
+        if table.peek().is_none() {
+            ui.label("No runs yet.");
+            return;
+        }
+        egui::Grid::new("high_scores").show(ui, |ui| {
+            for (rank, (index, entry)) in table.enumerate() {
+                let seconds = entry.time_survived as u32;
+                let row = [
+                    format!("{}.", rank + 1),
+                    entry.score.to_string(),
+                    format!("Level {}", entry.level),
+                    format_accuracy(entry.accuracy),
+                    format!("{}:{:02}", seconds / 60, seconds % 60),
+                ];
+                for text in row {
+                    if Some(index) == self.current {
+                        ui.label(egui::RichText::new(text).strong());
+                    } else {
+                        ui.label(text);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    }
+}
+
+fn high_scores_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "confluence-of-futility")
+        .map(|dirs| dirs.data_dir().join(HIGH_SCORES_FILE))
+}
+
+fn load_high_scores(mut high_scores: ResMut<HighScores>) {
+    let path = match &high_scores.path {
+        Some(path) => path.clone(),
+        None => return,
+    };
+    // A missing file just means nobody has finished a run yet
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    match ron::from_str::<HighScoresFile>(&text) {
+        Ok(file) if file.version == HIGH_SCORES_VERSION => {
+            high_scores.entries = file.entries;
+            high_scores.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        }
+        Ok(file) => warn!(
+            "High scores are from another version ({}), starting over",
+            file.version
+        ),
+        Err(err) => warn!("Could not read high scores {}: {}", path.display(), err),
+    }
+}
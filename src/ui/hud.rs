@@ -5,7 +5,13 @@ use bevy_tweening::{Animator, EaseFunction, Lens, Tween, TweeningType};
 
 use crate::{
     assets::{FontAssets, GameState},
-    player::Player,
+    enemies::EnemySpawnTimer,
+    modes::{GameMode, ModeState},
+    player::{
+        ultimate::{Ultimate, UltimateSettings},
+        Player,
+    },
+    ui::scoreboard::{format_accuracy, Scoreboard},
 };
 
 pub struct HudPlugin;
@@ -15,13 +21,19 @@ impl Plugin for HudPlugin {
         app.add_system_set(
             SystemSet::on_enter(GameState::Playing)
                 .with_system(setup_health_bar)
-                .with_system(setup_fail_message),
+                .with_system(setup_shield_bar)
+                .with_system(setup_ultimate_bar)
+                .with_system(setup_fail_message)
+                .with_system(setup_score_text),
         )
         .add_system_set(
             SystemSet::on_update(GameState::Playing)
                 .with_system(update_health_bar)
-                .with_system(update_fail_message)
-                .with_system(update_message),
+                .with_system(update_shield_bar)
+                .with_system(update_ultimate_bar)
+                .with_system(pick_message.before(update_message))
+                .with_system(update_message)
+                .with_system(update_score_text),
         );
     }
 }
@@ -29,6 +41,12 @@ impl Plugin for HudPlugin {
 #[derive(Component)]
 struct HealthBar;
 
+#[derive(Component)]
+struct ShieldBar;
+
+#[derive(Component)]
+struct UltimateBar;
+
 struct HealthBarLens {
     start: f32,
     end: f32,
@@ -41,6 +59,33 @@ impl Lens<Style> for HealthBarLens {
 }
 
 fn setup_health_bar(mut commands: Commands) {
+    spawn_bar(
+        &mut commands,
+        HealthBar,
+        40.0,
+        Color::rgba(1.0, 0.0, 0.0, 0.7),
+    );
+}
+
+fn setup_shield_bar(mut commands: Commands) {
+    spawn_bar(
+        &mut commands,
+        ShieldBar,
+        64.0,
+        Color::rgba(0.2, 0.6, 1.0, 0.7),
+    );
+}
+
+fn setup_ultimate_bar(mut commands: Commands) {
+    spawn_bar(
+        &mut commands,
+        UltimateBar,
+        88.0,
+        Color::rgba(1.0, 0.8, 0.2, 0.4),
+    );
+}
+
+fn spawn_bar(commands: &mut Commands, marker: impl Component, bottom: f32, color: Color) {
     commands
         .spawn_bundle(ButtonBundle {
             style: Style {
@@ -53,7 +98,7 @@ fn setup_health_bar(mut commands: Commands) {
                 },
                 position: Rect {
                     left: Val::Percent(5.0),
-                    bottom: Val::Px(40.0),
+                    bottom: Val::Px(bottom),
                     ..Default::default()
                 },
                 position_type: PositionType::Absolute,
@@ -69,7 +114,7 @@ fn setup_health_bar(mut commands: Commands) {
                         size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                         ..Default::default()
                     },
-                    color: Color::rgba(1.0, 0.0, 0.0, 0.7).into(),
+                    color: color.into(),
                     ..Default::default()
                 })
                 .insert(Animator::new(Tween::new(
@@ -81,7 +126,7 @@ fn setup_health_bar(mut commands: Commands) {
                         end: 100.0,
                     },
                 )))
-                .insert(HealthBar);
+                .insert(marker);
         });
 }
 
@@ -89,29 +134,60 @@ fn update_health_bar(
     players: Query<&Player, Changed<Player>>,
     mut health_bars: Query<(&mut Animator<Style>, &Style), With<HealthBar>>,
 ) {
-    for Player { health, max_health } in players.iter() {
+    for player in players.iter() {
         for (mut animator, style) in health_bars.iter_mut() {
-            if let Val::Percent(width) = style.size.width {
-                let health_percent = *health as f32 / *max_health as f32;
+            tween_bar(&mut animator, style, player.health / player.max_health);
+        }
+    }
+}
 
-                animator.set_tweenable(Tween::new(
-                    EaseFunction::QuadraticOut,
-                    TweeningType::Once,
-                    Duration::from_millis(400),
-                    HealthBarLens {
-                        start: width,
-                        end: health_percent * 100.0,
-                    },
-                ));
-            }
+fn update_shield_bar(
+    players: Query<&Player, Changed<Player>>,
+    mut shield_bars: Query<(&mut Animator<Style>, &Style), With<ShieldBar>>,
+) {
+    for player in players.iter() {
+        for (mut animator, style) in shield_bars.iter_mut() {
+            tween_bar(&mut animator, style, player.shield / player.max_shield);
         }
     }
 }
 
-#[derive(Component, PartialEq)]
+fn update_ultimate_bar(
+    settings: Res<UltimateSettings>,
+    ultimates: Query<&Ultimate, Changed<Ultimate>>,
+    mut ultimate_bars: Query<(&mut Animator<Style>, &Style, &mut UiColor), With<UltimateBar>>,
+) {
+    for ultimate in ultimates.iter() {
+        for (mut animator, style, mut color) in ultimate_bars.iter_mut() {
+            tween_bar(&mut animator, style, ultimate.fraction(&settings));
+            // Brighten when ready to use
+            let alpha = if ultimate.is_ready(&settings) {
+                0.9
+            } else {
+                0.4
+            };
+            *color = Color::rgba(1.0, 0.8, 0.2, alpha).into();
+        }
+    }
+}
+
+fn tween_bar(animator: &mut Animator<Style>, style: &Style, fraction: f32) {
+    if let Val::Percent(width) = style.size.width {
+        animator.set_tweenable(Tween::new(
+            EaseFunction::QuadraticOut,
+            TweeningType::Once,
+            Duration::from_millis(400),
+            HealthBarLens {
+                start: width,
+                end: fraction.max(0.0) * 100.0,
+            },
+        ));
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq)]
 pub enum ScreenMessage {
     Empty,
-    Failed,
     PressFire,
 }
 
@@ -151,19 +227,19 @@ fn setup_fail_message(mut commands: Commands, font_assets: Res<FontAssets>) {
         .insert(ScreenMessage::PressFire);
 }
 
-fn update_fail_message(
-    players: Query<&Player, Changed<Player>>,
+/// Asks the player to fire until they do, which starts the run by unpausing the spawn timer
+fn pick_message(
+    enemy_spawn_timer: Res<EnemySpawnTimer>,
     mut screen_messages: Query<&mut ScreenMessage>,
 ) {
-    for Player {
-        health,
-        max_health: _,
-    } in players.iter()
-    {
-        for mut screen_message in screen_messages.iter_mut() {
-            if health <= &0.0 {
-                *screen_message = ScreenMessage::Failed;
-            }
+    let message = if enemy_spawn_timer.0.paused() {
+        ScreenMessage::PressFire
+    } else {
+        ScreenMessage::Empty
+    };
+    for mut screen_message in screen_messages.iter_mut() {
+        if *screen_message != message {
+            *screen_message = message;
         }
     }
 }
@@ -172,9 +248,66 @@ fn update_message(mut screen_messages: Query<(&mut Text, &ScreenMessage), Change
     for (mut text, screen_message) in screen_messages.iter_mut() {
         text.sections[0].value = match *screen_message {
             ScreenMessage::Empty => "",
-            ScreenMessage::Failed => "You have failed to achieve victory.\nPress Tab to open menu",
             ScreenMessage::PressFire => "Press fire to begin",
         }
         .into();
     }
 }
+
+#[derive(Component)]
+struct ScoreText;
+
+fn setup_score_text(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text {
+                sections: vec![TextSection {
+                    value: "".to_string(),
+                    style: TextStyle {
+                        font: font_assets.fira_mono_medium.clone(),
+                        font_size: 32.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ScoreText);
+}
+
+fn update_score_text(
+    mode: Res<GameMode>,
+    mode_state: Res<ModeState>,
+    scoreboards: Query<&Scoreboard>,
+    mut score_texts: Query<&mut Text, With<ScoreText>>,
+) {
+    let scoreboard = match scoreboards.iter().next() {
+        Some(scoreboard) => scoreboard,
+        None => return,
+    };
+    let mut value = format!(
+        "Score: {} | Level: {} | Accuracy: {}",
+        scoreboard.score(),
+        scoreboard.level,
+        format_accuracy(scoreboard.accuracy())
+    );
+    if let Some(status) = mode.status(&mode_state, scoreboard) {
+        value = format!("{} | {}", value, status);
+    }
+    for mut text in score_texts.iter_mut() {
+        // Only touch the text when it changes so the UI isn't laid out again every frame
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
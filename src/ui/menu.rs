@@ -4,6 +4,7 @@ use bevy_egui::{
     egui::{self, Align2, FontDefinitions, Pos2},
     EguiContext,
 };
+use serde::{Deserialize, Serialize};
 
 #[cfg(debug_assertions)]
 use crate::assets::{
@@ -15,15 +16,49 @@ use crate::world::LevelAsset;
 
 use crate::{
     assets::GameState,
-    enemies::{EnemiesState, Enemy, EnemySpawnTimer},
-    player::{MovementSettings, Player},
-    world::level1,
+    input::{
+        gamepad::{shape_stick, GAMEPAD_MENU_ACCEPT},
+        ActionMap, ActiveDevice,
+    },
+    modes::{difficulty::Difficulty, GameMode},
+    player::{camera_effects::CameraEffectSettings, MovementSettings},
+    run::{
+        replay::{ReplayPlayer, ReplayRecorder},
+        snapshot::{SaveRun, SavedRun},
+        start_run, ResetRun,
+    },
 };
 
-use super::{hud::ScreenMessage, scoreboard::ScoreboardEvent};
+use super::high_scores::HighScores;
 
 pub struct MenuPlugin;
 
+/// Lets a gamepad step through menu buttons
+#[derive(Default)]
+pub struct MenuNavigation {
+    enabled: bool,
+    focused: usize,
+    activate: bool,
+    stick_released: bool,
+}
+
+impl MenuNavigation {
+    /// Draws a button that can also be focused and pressed with a gamepad
+    pub(super) fn button(&self, ui: &mut egui::Ui, index: usize, text: &str) -> bool {
+        let response = ui.button(text);
+        let focused = self.enabled && self.focused == index;
+        if focused {
+            response.request_focus();
+        }
+        response.clicked() || (focused && self.activate)
+    }
+
+    pub(super) fn clamp(&mut self, button_count: usize) {
+        self.focused = self.focused.min(button_count.saturating_sub(1));
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GamePreferences {
     pub high_res_textures: bool,
     pub light_shafts: bool,
@@ -42,11 +77,27 @@ impl Default for GamePreferences {
     }
 }
 
+impl GamePreferences {
+    /// Everything turned down, for slow machines
+    pub fn potato() -> Self {
+        GamePreferences {
+            high_res_textures: false,
+            light_shafts: false,
+            dynamic_shadows: false,
+            potato: true,
+        }
+    }
+}
+
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_update(GameState::Menu).with_system(startup_menu))
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(menu_ui))
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(menu_ui))
+            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(free_cursor))
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(lock_cursor))
             .insert_resource(GamePreferences::default())
+            .init_resource::<MenuNavigation>()
+            .add_system(gamepad_menu_navigation)
             .add_startup_system(setup_fonts);
     }
 }
@@ -61,18 +112,61 @@ pub fn setup_fonts(mut egui_context: ResMut<EguiContext>) {
     egui_context.ctx_mut().set_fonts(fonts);
 }
 
+fn gamepad_menu_navigation(
+    active_device: Res<ActiveDevice>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut navigation: ResMut<MenuNavigation>,
+) {
+    navigation.enabled = active_device.gamepad().is_some();
+    navigation.activate = active_device.just_pressed(&gamepad_buttons, GAMEPAD_MENU_ACCEPT);
+
+    let stick = shape_stick(
+        active_device.stick(
+            &gamepad_axes,
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+        ),
+        0.5,
+        1.0,
+    );
+    // The stick steps once per push, like the d-pad
+    let stick_up = navigation.stick_released && stick.y > 0.0;
+    let stick_down = navigation.stick_released && stick.y < 0.0;
+    navigation.stick_released = stick.y == 0.0;
+
+    if active_device.just_pressed(&gamepad_buttons, GamepadButtonType::DPadUp) || stick_up {
+        navigation.focused = navigation.focused.saturating_sub(1);
+    }
+    if active_device.just_pressed(&gamepad_buttons, GamepadButtonType::DPadDown) || stick_down {
+        navigation.focused += 1;
+    }
+}
+
 fn startup_menu(
     mut state: ResMut<State<GameState>>,
     mut windows: ResMut<Windows>,
     mut preferences: ResMut<GamePreferences>,
     mut egui_context: ResMut<EguiContext>,
-    asset_keys: ResMut<DynamicAssets>,
+    mut asset_keys: ResMut<DynamicAssets>,
     mut movement_settings: ResMut<MovementSettings>,
-    keys: Res<Input<KeyCode>>,
+    mut camera_effect_settings: ResMut<CameraEffectSettings>,
+    mut action_map: ResMut<ActionMap>,
+    mut navigation: ResMut<MenuNavigation>,
+    mut saved_run: ResMut<SavedRun>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    high_scores: Res<HighScores>,
+    (replay_recorder, mut replay_player): (Res<ReplayRecorder>, ResMut<ReplayPlayer>),
 ) {
     let window = windows.get_primary_mut().unwrap();
 
     if window.is_focused() && !window.cursor_locked() {
+        let can_resume = saved_run.exists();
+        let last_replay = replay_recorder.last();
+        let watch_button = if can_resume { 2 } else { 1 };
+        let mode_button = watch_button + last_replay.map_or(0, |_| 1);
+        navigation.clamp(mode_button + 2);
         egui::Window::new("CONFLUENCE OF FUTILITY")
             .resizable(false)
             .collapsible(false)
@@ -83,11 +177,21 @@ fn startup_menu(
             .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
             .show(egui_context.ctx_mut(), |ui| {
                 ui.vertical_centered_justified(|ui| {
-                    if ui.button("START").clicked() {
-                        level1::set_textures_res(asset_keys, preferences.high_res_textures);
-                        state
-                            .set(GameState::Loading)
-                            .expect("Failed to change state");
+                    let start = navigation.button(ui, 0, "START");
+                    // The saved run is restored once the new run has started
+                    let resume = can_resume
+                        && navigation.button(ui, 1, "RESUME SAVED RUN")
+                        && saved_run.resume();
+                    let watch = last_replay.map_or(false, |path| {
+                        navigation.button(ui, watch_button, "WATCH LAST RUN")
+                            && replay_player.watch(path)
+                    });
+                    if start || resume || watch {
+                        start_run(
+                            &mut state,
+                            Some(&mut *asset_keys),
+                            preferences.high_res_textures,
+                        );
                         window.set_cursor_position(Vec2::new(
                             window.width() / 4.0,
                             window.height() / 4.0,
@@ -95,9 +199,25 @@ fn startup_menu(
                         window.set_cursor_lock_mode(true);
                         window.set_cursor_visibility(false);
                     }
+                    if navigation.button(ui, mode_button, &format!("MODE: {}", mode.label())) {
+                        *mode = mode.next();
+                    }
+                    ui.label(mode.description());
+                    if navigation.button(
+                        ui,
+                        mode_button + 1,
+                        &format!("DIFFICULTY: {}", difficulty.label()),
+                    ) {
+                        *difficulty = difficulty.next();
+                    }
+                });
+                ui.collapsing("High scores", |ui| {
+                    high_scores.build_ui(ui, *mode, *difficulty);
                 });
                 ui.collapsing("Preferences", |ui| {
-                    movement_settings.build_ui(ui, keys);
+                    movement_settings.build_ui(ui);
+                    camera_effect_settings.build_ui(ui);
+                    ui.collapsing("Controls", |ui| action_map.build_ui(ui));
                     ui.checkbox(
                         &mut preferences.high_res_textures,
                         "High resolution textures",
@@ -110,55 +230,66 @@ fn startup_menu(
     }
 }
 
+/// Locks the cursor for playing, or frees it for the menus
+fn grab_cursor(window: &mut Window, grab: bool) {
+    if grab && (!window.is_focused() || !window.cursor_locked()) {
+        window.set_cursor_position(Vec2::new(window.width() / 4.0, window.height() / 4.0));
+    }
+    window.set_cursor_lock_mode(grab);
+    window.set_cursor_visibility(!grab);
+}
+
+fn free_cursor(mut windows: ResMut<Windows>) {
+    grab_cursor(windows.get_primary_mut().unwrap(), false);
+}
+
+fn lock_cursor(mut windows: ResMut<Windows>) {
+    grab_cursor(windows.get_primary_mut().unwrap(), true);
+}
+
+fn resume_game(state: &mut State<GameState>) {
+    // Fails when another change is already queued, the button can be pressed again
+    let _ = state.pop();
+}
+
 fn menu_ui(
-    mut commands: Commands,
-    mut windows: ResMut<Windows>,
+    mut state: ResMut<State<GameState>>,
+    windows: Res<Windows>,
     mut egui_context: ResMut<EguiContext>,
     #[cfg(debug_assertions)] mut custom_materials: ResMut<Assets<CustomMaterial>>,
     #[cfg(debug_assertions)] mut light_shaft_materials: ResMut<Assets<LightShaftMaterial>>,
     #[cfg(debug_assertions)] mut orb_materials: ResMut<Assets<OrbMaterial>>,
     #[cfg(debug_assertions)] mut level_asset_query: Query<&mut LevelAsset>,
     mut movement_settings: ResMut<MovementSettings>,
-    keys: Res<Input<KeyCode>>,
-    mut players: Query<(&mut Player, &mut Transform)>,
-    enemies: Query<Entity, With<Enemy>>,
-    mut enemies_state: ResMut<EnemiesState>,
-    mut scoreboard_events: EventWriter<ScoreboardEvent>,
-    mut enemy_spawn_timer: ResMut<EnemySpawnTimer>,
-    mut screen_messages: Query<&mut ScreenMessage>,
+    mut camera_effect_settings: ResMut<CameraEffectSettings>,
+    mut action_map: ResMut<ActionMap>,
+    mut reset_events: EventWriter<ResetRun>,
+    mut save_events: EventWriter<SaveRun>,
+    mut navigation: ResMut<MenuNavigation>,
 ) {
-    let window = windows.get_primary_mut().unwrap();
-    if window.is_focused() && !window.cursor_locked() {
+    let window = windows.get_primary().unwrap();
+    if window.is_focused() {
+        navigation.clamp(3);
         egui::Window::new("Preferences")
             .resizable(false)
             .collapsible(false)
             .default_pos(Pos2::new(10.0, 200.0))
             .show(egui_context.ctx_mut(), |ui| {
                 ui.vertical_centered_justified(|ui| {
-                    if ui.button("Continue").clicked() {
-                        window.set_cursor_lock_mode(true);
-                        window.set_cursor_visibility(false);
+                    if navigation.button(ui, 0, "Continue") {
+                        resume_game(&mut state);
                     }
-                    if ui.button("Restart").clicked() {
-                        // TODO move elsewhere, trigger with event
-                        if let Some((mut player, mut trans)) = players.iter_mut().next() {
-                            player.health = player.max_health;
-                            *trans = Transform::from_xyz(0.0, 3.0, 100.0);
-                        }
-                        for entity in enemies.iter() {
-                            commands.entity(entity).despawn_recursive();
-                        }
-                        scoreboard_events.send(ScoreboardEvent::Reset);
-                        *enemies_state = EnemiesState::default();
-                        enemy_spawn_timer.0.pause();
-                        for mut screen_message in screen_messages.iter_mut() {
-                            *screen_message = ScreenMessage::PressFire;
-                        }
-                        window.set_cursor_lock_mode(true);
-                        window.set_cursor_visibility(false);
+                    if navigation.button(ui, 1, "Restart") {
+                        reset_events.send(ResetRun);
+                        resume_game(&mut state);
+                    }
+                    if navigation.button(ui, 2, "Save Run") {
+                        save_events.send(SaveRun);
                     }
                 });
-                movement_settings.build_ui(ui, keys);
+                movement_settings.build_ui(ui);
+                camera_effect_settings.build_ui(ui);
+                ui.collapsing("Controls", |ui| action_map.build_ui(ui));
             });
 
         #[cfg(debug_assertions)]
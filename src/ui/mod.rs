@@ -1,9 +1,15 @@
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
 
-use self::{fps::FpsPlugin, hud::HudPlugin, menu::MenuPlugin, scoreboard::ScoreboardPlugin};
+use self::{
+    damage_indicators::DamageIndicatorPlugin, fps::FpsPlugin, game_over::GameOverPlugin,
+    high_scores::HighScoresPlugin, hud::HudPlugin, menu::MenuPlugin,
+};
 
+pub mod damage_indicators;
 pub mod fps;
+pub mod game_over;
+pub mod high_scores;
 pub mod hud;
 pub mod menu;
 pub mod scoreboard;
@@ -15,12 +21,21 @@ impl Plugin for UiPlugin {
         app.add_plugin(EguiPlugin)
             .add_plugin(MenuPlugin)
             .add_plugin(HudPlugin)
+            .add_plugin(DamageIndicatorPlugin)
             .add_plugin(FpsPlugin)
-            .add_plugin(ScoreboardPlugin)
-            .add_startup_system(setup_ui_camera);
+            .add_plugin(GameOverPlugin)
+            .add_plugin(HighScoresPlugin)
+            .add_startup_system(setup_ui_camera)
+            .add_startup_system(mouse_startup);
     }
 }
 
 fn setup_ui_camera(mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
 }
+
+fn mouse_startup(mut windows: ResMut<Windows>) {
+    let window = windows.get_primary_mut().unwrap();
+    window.set_cursor_lock_mode(false);
+    window.set_cursor_visibility(true);
+}
@@ -1,105 +1,176 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
 
-use crate::assets::{FontAssets, GameState};
+use crate::{
+    assets::GameState,
+    enemies::{EnemyKind, EnemySpawnTimer},
+    player::{Player, WeaponKind},
+    run::ResetRun,
+    GameClock, GameplaySystem,
+};
 
+/// Perfect accuracy multiplies the kill score by 1.0 plus this
+const ACCURACY_BONUS: f32 = 0.5;
+
+/// Keeps score of the run, the HUD shows it
 pub struct ScoreboardPlugin;
 
 impl Plugin for ScoreboardPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Scoreboard {
-            kills: 0,
-            hits: 0,
-            misses: 0,
-            level: 0,
-        })
-        .add_event::<ScoreboardEvent>()
-        .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_scoreboard))
-        .add_system_set(
-            SystemSet::on_update(GameState::Playing)
-                .with_system(scoreboard_ui)
-                .with_system(handle_scoreboard_event),
-        );
+        app.add_event::<ScoreboardEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_scoreboard))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(
+                    track_time_survived
+                        .label(GameplaySystem::Scoreboard)
+                        .after(GameplaySystem::Modes)
+                        .before(handle_scoreboard_event),
+                ),
+            )
+            // Also runs outside of Playing so a reset from the game over screen is never missed.
+            // After the modes, so a bonus paid in the frame the run ends still counts.
+            .add_system(
+                handle_scoreboard_event
+                    .label(GameplaySystem::Scoreboard)
+                    .after(GameplaySystem::Modes),
+            );
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-#[allow(clippy::enum_variant_names)] // TODO: Remove this when the scoreboard starts being used
 pub enum ScoreboardEvent {
-    Kill,
+    Kill(EnemyKind),
     LevelUp,
-    _Hit,
-    _Miss,
-    Reset,
+    /// A shot that hit at least one enemy
+    Hit(WeaponKind),
+    /// A shot that hit no enemies, `hit_world` if it stopped on level geometry
+    Miss {
+        weapon: WeaponKind,
+        hit_world: bool,
+    },
+    /// Points awarded by the game mode, like for clearing a wave
+    Bonus(usize),
 }
 
-#[derive(Component, Default)]
-struct Scoreboard {
-    pub kills: usize,
+/// Shot results for one weapon
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShotStats {
     pub hits: usize,
+    pub world_hits: usize,
     pub misses: usize,
+}
+
+impl ShotStats {
+    /// Fraction of shots that hit an enemy, if any were fired
+    pub fn accuracy(&self) -> Option<f32> {
+        let shots = self.hits + self.world_hits + self.misses;
+        if shots == 0 {
+            None
+        } else {
+            Some(self.hits as f32 / shots as f32)
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scoreboard {
+    pub kills: usize,
+    pub shots: HashMap<WeaponKind, ShotStats>,
     pub level: usize,
+    pub kills_by_kind: HashMap<EnemyKind, usize>,
+    /// Seconds since the run started, stops when the player dies
+    pub time_survived: f32,
+    /// Points from the game mode on top of the kills
+    pub bonus: usize,
 }
 
-fn setup_scoreboard(mut commands: Commands, font_assets: Res<FontAssets>) {
-    commands
-        .spawn_bundle(TextBundle {
-            text: Text {
-                sections: vec![TextSection {
-                    value: "".to_string(),
-                    style: TextStyle {
-                        font: font_assets.fira_mono_medium.clone(),
-                        font_size: 32.0,
-                        color: Color::WHITE,
-                    },
-                }],
-                ..Default::default()
-            },
-            style: Style {
-                position_type: PositionType::Absolute,
-                position: Rect {
-                    top: Val::Px(5.0),
-                    left: Val::Px(5.0),
-                    ..Default::default()
-                },
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .insert(Scoreboard::default());
+impl Scoreboard {
+    pub fn score(&self) -> usize {
+        let accuracy = self.accuracy().unwrap_or(0.0);
+        ((self.kills * 100) as f32 * (1.0 + ACCURACY_BONUS * accuracy)).round() as usize
+            + self.bonus
+    }
+
+    pub fn weapon_stats(&self, weapon: WeaponKind) -> ShotStats {
+        self.shots.get(&weapon).copied().unwrap_or_default()
+    }
+
+    /// Shot results across every weapon
+    pub fn total_stats(&self) -> ShotStats {
+        self.shots
+            .values()
+            .fold(ShotStats::default(), |total, stats| ShotStats {
+                hits: total.hits + stats.hits,
+                world_hits: total.world_hits + stats.world_hits,
+                misses: total.misses + stats.misses,
+            })
+    }
+
+    /// Fraction of shots that hit an enemy, if any were fired
+    pub fn accuracy(&self) -> Option<f32> {
+        self.total_stats().accuracy()
+    }
+}
+
+fn setup_scoreboard(mut commands: Commands) {
+    commands.spawn().insert(Scoreboard::default());
 }
 
-fn scoreboard_ui(mut scoreboards: Query<(&mut Text, &Scoreboard), Changed<Scoreboard>>) {
-    for (mut text, scoreboard) in scoreboards.iter_mut() {
-        let kills = scoreboard.kills;
-        text.sections[0].value = format!("Score: {} | Level: {}", kills * 100, scoreboard.level);
+/// Accuracy as a whole percentage, or a dash before any shots
+pub fn format_accuracy(accuracy: Option<f32>) -> String {
+    accuracy.map_or("-".to_string(), |accuracy| {
+        format!("{:.0}%", accuracy * 100.0)
+    })
+}
+
+fn track_time_survived(
+    clock: Res<GameClock>,
+    enemy_spawn_timer: Res<EnemySpawnTimer>,
+    players: Query<&Player>,
+    mut scoreboards: Query<&mut Scoreboard>,
+) {
+    // The run starts when the player first fires, which unpauses the spawn timer
+    if enemy_spawn_timer.0.paused() || players.iter().all(|player| player.health <= 0.0) {
+        return;
+    }
+    for mut scoreboard in scoreboards.iter_mut() {
+        scoreboard.time_survived += clock.delta_seconds();
     }
 }
 
 fn handle_scoreboard_event(
     mut scoreboards: Query<&mut Scoreboard>,
     mut events: EventReader<ScoreboardEvent>,
+    mut reset_events: EventReader<ResetRun>,
 ) {
+    let reset = reset_events.iter().count() > 0;
     for mut scoreboard in scoreboards.iter_mut() {
+        if reset {
+            *scoreboard = Scoreboard::default();
+        }
         for event in events.iter() {
             match event {
-                ScoreboardEvent::_Hit => {
-                    scoreboard.hits += 1;
+                ScoreboardEvent::Hit(weapon) => {
+                    scoreboard.shots.entry(*weapon).or_default().hits += 1;
                 }
-                ScoreboardEvent::Kill => {
+                ScoreboardEvent::Kill(kind) => {
                     scoreboard.kills += 1;
+                    *scoreboard.kills_by_kind.entry(*kind).or_default() += 1;
                 }
-                ScoreboardEvent::_Miss => {
-                    scoreboard.misses += 1;
-                }
-                ScoreboardEvent::Reset => {
-                    scoreboard.hits = 0;
-                    scoreboard.misses = 0;
-                    scoreboard.kills = 0;
-                    scoreboard.level = 0;
+                ScoreboardEvent::Miss { weapon, hit_world } => {
+                    let stats = scoreboard.shots.entry(*weapon).or_default();
+                    if *hit_world {
+                        stats.world_hits += 1;
+                    } else {
+                        stats.misses += 1;
+                    }
                 }
                 ScoreboardEvent::LevelUp => {
                     scoreboard.level += 1;
                 }
+                ScoreboardEvent::Bonus(points) => {
+                    scoreboard.bonus += points;
+                }
             }
         }
     }
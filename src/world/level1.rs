@@ -2,15 +2,15 @@ use bevy::{
     math::{EulerRot, Quat, Vec3},
     pbr::{DirectionalLight, DirectionalLightBundle, MaterialMeshBundle},
     prelude::*,
-    render::mesh::{Indices, VertexAttributeValues},
 };
 use bevy_asset_loader::{DynamicAsset, DynamicAssets};
+use gltf::{buffer::Source, Gltf};
 use heron::{
     rapier_plugin::{
         nalgebra::Point3,
         rapier3d::{math::Real, prelude::ColliderBuilder},
     },
-    CollisionLayers, CollisionShape, CustomCollisionShape, PhysicMaterial, RigidBody,
+    CollisionShape, CustomCollisionShape, PhysicMaterial, RigidBody,
 };
 
 use crate::{
@@ -29,20 +29,46 @@ use crate::{
     },
     enemies::Waypoints,
     ui::menu::GamePreferences,
-    Layer,
 };
 
-use super::LevelAsset;
+use super::{level_collision_layers, LevelAsset, LevelMesh};
 
+/// The models the level's colliders are built from, embedded so they don't wait on the renderer
+/// to load them as meshes
+const COLLISION_MODELS: [(&[u8], LevelMesh); 5] = [
+    (
+        include_bytes!("../../assets/models/level1/pillars.gltf"),
+        LevelMesh::Pillars,
+    ),
+    (
+        include_bytes!("../../assets/models/level1/spheres.gltf"),
+        LevelMesh::Spheres,
+    ),
+    (
+        include_bytes!("../../assets/models/level1/large_ceiling_supports.gltf"),
+        LevelMesh::LargeCeilingSupports,
+    ),
+    (
+        include_bytes!("../../assets/models/level1/walls.gltf"),
+        LevelMesh::Walls,
+    ),
+    (
+        include_bytes!("../../assets/models/level1/spheres_base.gltf"),
+        LevelMesh::SpheresBase,
+    ),
+];
+/// How the models embed their buffers
+const DATA_URI: &str = "data:application/octet-stream;base64,";
+
+/// The level's models, lights and materials
 pub struct LevelOnePlugin;
 
 impl Plugin for LevelOnePlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(
             SystemSet::on_enter(GameState::Playing)
-                .with_system(setup_level_one)
                 //.with_system(spawn_demo_cubes)
-                .with_system(spawn_waypoints),
+                .with_system(setup_level_one),
         )
         .add_system(update_light_shaft_material_time)
         .add_system(update_orb_material_time);
@@ -80,16 +106,12 @@ fn spawn_demo_cubes(
                     restitution: 0.7,
                     ..Default::default()
                 })
-                .insert(
-                    CollisionLayers::none()
-                        .with_group(Layer::World)
-                        .with_masks(Layer::all()),
-                );
+                .insert(level_collision_layers());
         }
     }
 }
 
-pub fn set_textures_res(mut asset_keys: ResMut<DynamicAssets>, high_res: bool) {
+pub fn set_textures_res(asset_keys: &mut DynamicAssets, high_res: bool) {
     let sm = if high_res { "" } else { "sm/" };
     for s in [
         "large_ceiling_supports",
@@ -118,7 +140,6 @@ pub fn set_textures_res(mut asset_keys: ResMut<DynamicAssets>, high_res: bool) {
 
 fn setup_level_one(
     mut commands: Commands,
-    mesh_assets: Res<Assets<Mesh>>,
     image_assets: Res<ImageAssets>,
     model_assets: Res<ModelAssets>,
     mut custom_materials: ResMut<Assets<CustomMaterial>>,
@@ -284,29 +305,6 @@ fn setup_level_one(
             ],
         });
 
-        let mesh = mesh_assets.get(model.clone()).unwrap();
-
-        let vertices: Vec<Point3<Real>> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
-            None => panic!("Mesh does not contain vertex positions"),
-            Some(vertex_values) => match &vertex_values {
-                VertexAttributeValues::Float32x3(positions) => positions
-                    .iter()
-                    .map(|[x, y, z]| Point3::new(*x, *y, *z))
-                    .collect(),
-                _ => panic!("Unexpected types in {:?}", Mesh::ATTRIBUTE_POSITION),
-            },
-        };
-
-        let indices: Vec<_> = match mesh.indices().unwrap() {
-            Indices::U16(_) => {
-                panic!("expected u32 indices");
-            }
-            Indices::U32(indices) => indices
-                .chunks(3)
-                .map(|chunk| [chunk[0], chunk[1], chunk[2]])
-                .collect(),
-        };
-
         commands
             .spawn()
             .insert_bundle(MaterialMeshBundle {
@@ -315,19 +313,10 @@ fn setup_level_one(
                 material: material.clone(),
                 ..Default::default()
             })
-            .insert(RigidBody::Static)
-            .insert(CollisionShape::Custom {
-                shape: CustomCollisionShape::new(ColliderBuilder::trimesh(vertices, indices)),
-            })
             .insert(LevelAsset::CustomMaterial {
                 properties: material_properties,
                 handle: material,
-            })
-            .insert(
-                CollisionLayers::none()
-                    .with_group(Layer::World)
-                    .with_masks(Layer::all()),
-            );
+            });
     }
     if preferences.dynamic_shadows {
         //Bevy Sun
@@ -380,7 +369,66 @@ fn setup_level_one(
     });
 }
 
-fn spawn_waypoints(
+/// Static colliders for the level's models, read from the glTF files rather than the loaded
+/// meshes so a headless app has the level too
+pub fn spawn_level_colliders(mut commands: Commands) {
+    for (gltf, level_mesh) in COLLISION_MODELS {
+        let (vertices, indices) = read_trimesh(gltf);
+        commands.spawn_bundle((
+            Transform::default(),
+            GlobalTransform::default(),
+            RigidBody::Static,
+            CollisionShape::Custom {
+                shape: CustomCollisionShape::new(ColliderBuilder::trimesh(vertices, indices)),
+            },
+            level_mesh,
+            level_collision_layers(),
+        ));
+    }
+}
+
+/// Vertices and triangles of every mesh in a glTF file with embedded buffers
+fn read_trimesh(bytes: &[u8]) -> (Vec<Point3<Real>>, Vec<[u32; 3]>) {
+    let gltf = Gltf::from_slice(bytes).expect("Level models are valid glTF");
+    let buffers = gltf
+        .buffers()
+        .map(|buffer| match buffer.source() {
+            Source::Uri(uri) => uri
+                .strip_prefix(DATA_URI)
+                .and_then(|data| base64::decode(data).ok())
+                .expect("Level models embed their buffers"),
+            Source::Bin => gltf.blob.clone().expect("Level models embed their buffers"),
+        })
+        .collect::<Vec<_>>();
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for mesh in gltf.meshes() {
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+            let offset = vertices.len() as u32;
+            vertices.extend(
+                reader
+                    .read_positions()
+                    .expect("Level meshes have positions")
+                    .map(|[x, y, z]| Point3::new(x, y, z)),
+            );
+            let triangles = reader
+                .read_indices()
+                .expect("Level meshes are indexed")
+                .into_u32()
+                .map(|index| index + offset)
+                .collect::<Vec<_>>();
+            indices.extend(
+                triangles
+                    .chunks(3)
+                    .map(|chunk| [chunk[0], chunk[1], chunk[2]]),
+            );
+        }
+    }
+    (vertices, indices)
+}
+
+pub fn spawn_waypoints(
     //mut commands: Commands,
     //mut meshes: ResMut<Assets<Mesh>>,
     //mut materials: ResMut<Assets<StandardMaterial>>,
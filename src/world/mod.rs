@@ -1,23 +1,43 @@
 use bevy::prelude::*;
+use heron::CollisionLayers;
 
-use crate::assets::{
-    custom_material::{CustomMaterial, MaterialProperties},
-    light_shaft_material::{LightShaftMaterial, LightShaftProperties},
-    orb_material::{OrbMaterial, OrbProperties},
+use crate::{
+    assets::{
+        custom_material::{CustomMaterial, MaterialProperties},
+        light_shaft_material::{LightShaftMaterial, LightShaftProperties},
+        orb_material::{OrbMaterial, OrbProperties},
+        GameState,
+    },
+    Layer,
 };
 
-use self::level1::LevelOnePlugin;
+use self::level1::{spawn_level_colliders, spawn_waypoints};
 
 pub mod level1;
 
+/// The level's colliders and the paths enemies take through it, its models come with
+/// [`LevelOnePlugin`]
+///
+/// [`LevelOnePlugin`]: level1::LevelOnePlugin
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(LevelOnePlugin);
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing)
+                .with_system(spawn_level_colliders)
+                .with_system(spawn_waypoints),
+        );
     }
 }
 
+/// Collision layers of the level's static colliders, which block everything
+pub fn level_collision_layers() -> CollisionLayers {
+    CollisionLayers::none()
+        .with_group(Layer::World)
+        .with_masks(Layer::all())
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Component, Debug)]
 pub enum LevelAsset {
@@ -35,6 +55,16 @@ pub enum LevelAsset {
     },
 }
 
+/// Which part of the level a static collider belongs to, used to pick surface sounds
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LevelMesh {
+    Pillars,
+    Spheres,
+    LargeCeilingSupports,
+    Walls,
+    SpheresBase,
+}
+
 //#[derive(Component, Debug)]
 //pub struct LevelAsset {
 //    pub material_properties: MaterialProperties,
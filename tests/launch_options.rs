@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use bevy::window::WindowMode;
+use game::{run::launch::LaunchOptions, Difficulty, GameMode};
+
+fn parse(args: &[&str]) -> Result<LaunchOptions, String> {
+    LaunchOptions::from_args(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn no_arguments_opens_the_menu() {
+    let options = parse(&[]).unwrap();
+    assert!(!options.start);
+    assert!(options.audio);
+    assert_eq!(options.frames, None);
+    assert_eq!(options.window_mode, WindowMode::Windowed);
+    assert!(!options.headless());
+}
+
+#[test]
+fn reads_every_option() {
+    let options = parse(&[
+        "--seed",
+        "42",
+        "--mode",
+        "boss-rush",
+        "--difficulty",
+        "Nightmare",
+        "--level",
+        "3",
+        "--potato",
+        "--borderless",
+        "--resolution",
+        "1920x1080",
+        "--no-audio",
+        "--frames",
+        "600",
+    ])
+    .unwrap();
+    assert_eq!(options.seed, Some(42));
+    assert_eq!(options.mode, Some(GameMode::BossRush));
+    assert_eq!(options.difficulty, Some(Difficulty::Nightmare));
+    assert_eq!(options.start_level, 3);
+    assert!(options.preferences.unwrap().potato);
+    assert_eq!(options.window_mode, WindowMode::BorderlessFullscreen);
+    assert_eq!(options.resolution, Some((1920.0, 1080.0)));
+    assert!(!options.audio);
+    assert_eq!(options.frames, Some(600));
+    // Counting frames only makes sense in a run
+    assert!(options.start);
+}
+
+#[test]
+fn reads_bot_and_replay_options() {
+    let options = parse(&["--autoplay", "5", "--start", "--replay", "run.ron"]).unwrap();
+    assert_eq!(options.autoplay, Some(5));
+    assert!(options.start);
+    assert_eq!(options.replay, Some(PathBuf::from("run.ron")));
+    assert!(!options.verify_replay);
+    assert!(options.headless());
+
+    let options = parse(&["--verify-replay", "run.ron"]).unwrap();
+    assert_eq!(options.replay, Some(PathBuf::from("run.ron")));
+    assert!(options.verify_replay);
+    assert!(options.headless());
+}
+
+#[test]
+fn autoplay_runs_are_optional() {
+    let options = parse(&["--autoplay", "--seed", "7"]).unwrap();
+    assert_eq!(options.autoplay, Some(0));
+    assert_eq!(options.seed, Some(7));
+}
+
+#[test]
+fn rejects_unknown_flags() {
+    let err = parse(&["--start", "--fast"]).unwrap_err();
+    assert!(err.contains("--fast"));
+    assert!(parse(&["run.ron"]).is_err());
+}
+
+#[test]
+fn rejects_bad_values() {
+    assert!(parse(&["--seed", "many"]).is_err());
+    assert!(parse(&["--mode", "deathmatch"]).is_err());
+    assert!(parse(&["--resolution", "1920"]).is_err());
+    assert!(parse(&["--frames"]).is_err());
+}
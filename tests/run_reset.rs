@@ -0,0 +1,117 @@
+mod support;
+
+use bevy::prelude::*;
+use game::{
+    run::RunScoped, Action, EnemiesState, EnemyKind, GameMode, InputState, ModeState, Scoreboard,
+};
+use support::Scenario;
+
+/// How far the player may come to rest from where a fresh run put them
+const POSITION_TOLERANCE: f32 = 0.01;
+
+/// Everything a reset should put back the way it was
+#[derive(Debug, PartialEq)]
+struct RunSnapshot {
+    health: f32,
+    shield: f32,
+    pitch: f32,
+    yaw: f32,
+    enemies_state: EnemiesState,
+    scoreboard: Scoreboard,
+    mode_state: ModeState,
+    /// Enemies, bullets and everything else that only lives for one run
+    run_scoped: usize,
+}
+
+fn snapshot(scenario: &mut Scenario) -> RunSnapshot {
+    let player = scenario.player();
+    let (health, shield) = (player.health, player.shield);
+    let input_state = scenario.app.world.resource::<InputState>();
+    let (pitch, yaw) = (input_state.pitch, input_state.yaw);
+    let run_scoped = scenario
+        .app
+        .world
+        .query_filtered::<Entity, With<RunScoped>>()
+        .iter(&scenario.app.world)
+        .count();
+    RunSnapshot {
+        health,
+        shield,
+        pitch,
+        yaw,
+        enemies_state: scenario.app.world.resource::<EnemiesState>().clone(),
+        scoreboard: scenario.scoreboard().clone(),
+        mode_state: scenario.app.world.resource::<ModeState>().clone(),
+        run_scoped,
+    }
+}
+
+/// Kills an enemy, which starts the run, leaves another alive, walks off, takes a hit, looks
+/// around and has a bullet still on its way when it returns
+fn play(scenario: &mut Scenario) {
+    let position = scenario.in_sight(20.0);
+    scenario.spawn_enemy(EnemyKind::Orbie, position);
+    let other = scenario.player_position() + Vec3::new(30.0, 5.0, 0.0);
+    scenario.spawn_enemy(EnemyKind::Orbie, other);
+    scenario.step(2);
+    scenario.tap(Action::Fire);
+
+    let player = scenario.player_position();
+    scenario.shoot_bullet(player + Vec3::new(0.0, 0.0, -10.0), Vec3::Z, 100.0, 8.0);
+    scenario.hold(Action::MoveForward);
+    scenario.step(30);
+    scenario.release(Action::MoveForward);
+    scenario.shoot_bullet(player + Vec3::new(0.0, 0.0, -50.0), Vec3::Z, 100.0, 8.0);
+    scenario.app.world.insert_resource(InputState {
+        pitch: 0.3,
+        yaw: -1.1,
+    });
+    scenario.step(2);
+}
+
+fn assert_matches_fresh_start(played: &mut Scenario, fresh: &mut Scenario) {
+    assert_eq!(snapshot(played), snapshot(fresh));
+    let distance = played.player_position().distance(fresh.player_position());
+    assert!(
+        distance < POSITION_TOLERANCE,
+        "the player came to rest {} m from a fresh start",
+        distance
+    );
+}
+
+#[test]
+fn reset_matches_fresh_start() {
+    let mut fresh = Scenario::with_mode(GameMode::Waves);
+    let mut played = Scenario::with_mode(GameMode::Waves);
+    play(&mut played);
+    assert_ne!(snapshot(&mut played), snapshot(&mut fresh));
+
+    played.reset();
+    assert_matches_fresh_start(&mut played, &mut fresh);
+}
+
+#[test]
+fn reset_twice_in_a_row() {
+    let mut fresh = Scenario::with_mode(GameMode::Waves);
+    let mut played = Scenario::with_mode(GameMode::Waves);
+    play(&mut played);
+    played.reset();
+    play(&mut played);
+    played.reset();
+    assert_matches_fresh_start(&mut played, &mut fresh);
+}
+
+#[test]
+fn nothing_resets_without_the_event() {
+    let mut fresh = Scenario::with_mode(GameMode::Waves);
+    let mut played = Scenario::with_mode(GameMode::Waves);
+    play(&mut played);
+    played.step(2);
+
+    let (played, fresh) = (snapshot(&mut played), snapshot(&mut fresh));
+    assert_eq!(played.enemies_state.enemies_killed, 1);
+    assert_eq!(played.scoreboard.kills, 1);
+    assert_eq!(played.mode_state.wave, 1);
+    assert!(played.run_scoped > 0);
+    assert!(played.health + played.shield < fresh.health + fresh.shield);
+}
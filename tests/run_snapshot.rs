@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use game::{
+    run::{snapshot::RunSnapshot, GameRng, RunScoped},
+    spawn_enemy_in_world, Difficulty, EnemiesState, Enemy, EnemyKind, EnemySpawnTimer, GameClock,
+    GameMode, InputState, ModeState, Player, Scoreboard,
+};
+use rand::Rng;
+
+/// A world with what a run needs, as it looks before the first shot
+fn fresh_run() -> World {
+    let mut world = World::new();
+    world.insert_resource(GameClock::default());
+    world.insert_resource(GameMode::default());
+    world.insert_resource(Difficulty::default());
+    world.insert_resource(ModeState::default());
+    world.insert_resource(EnemiesState::default());
+    world.insert_resource(EnemySpawnTimer::default());
+    world.insert_resource(GameRng::seeded(1));
+    world.insert_resource(InputState::default());
+    world
+        .spawn()
+        .insert_bundle((Transform::from_xyz(0.0, 3.0, 100.0), Player::default()));
+    world.spawn().insert(Scoreboard::default());
+    world
+}
+
+fn mid_run() -> World {
+    let mut world = fresh_run();
+    world.resource_mut::<GameClock>().tick(83.5);
+    world.insert_resource(GameMode::Waves);
+    world.insert_resource(Difficulty::Hard);
+    world.insert_resource(ModeState {
+        wave: 3,
+        wave_remaining: 5,
+        ..Default::default()
+    });
+    {
+        let mut enemies_state = world.resource_mut::<EnemiesState>();
+        enemies_state.enemies_killed = 27;
+        enemies_state.current_level = 1;
+        enemies_state.destinations = [4, 7, 2];
+        enemies_state.last_time_player_took_damage = 80.25;
+    }
+    world.resource_mut::<EnemySpawnTimer>().0.unpause();
+    {
+        // Roll a few times so the rng is somewhere past its seed
+        let mut rng = world.resource_mut::<GameRng>();
+        for _ in 0..10 {
+            rng.0.gen::<u32>();
+        }
+    }
+    *world.resource_mut::<InputState>() = InputState {
+        pitch: -0.3,
+        yaw: 2.1,
+    };
+    for (i, kind) in EnemyKind::all().into_iter().enumerate() {
+        let transform = Transform::from_xyz(10.0 * i as f32, 5.0, -40.0)
+            .with_rotation(Quat::from_rotation_y(i as f32));
+        let entity = spawn_enemy_in_world(&mut world, kind, transform);
+        world.get_mut::<Enemy>(entity).unwrap().health -= 100 * (i as i32 + 1);
+    }
+    for (mut transform, mut player) in world
+        .query::<(&mut Transform, &mut Player)>()
+        .iter_mut(&mut world)
+    {
+        transform.translation = Vec3::new(12.0, 3.0, 40.0);
+        player.health = 640.0;
+        player.shield = 0.0;
+    }
+    for mut scoreboard in world.query::<&mut Scoreboard>().iter_mut(&mut world) {
+        scoreboard.kills = 27;
+        scoreboard.level = 1;
+        scoreboard.time_survived = 83.5;
+        scoreboard.kills_by_kind.insert(EnemyKind::Orbie, 15);
+        scoreboard.kills_by_kind.insert(EnemyKind::Laserie, 12);
+    }
+    world
+}
+
+#[test]
+fn snapshot_survives_the_file_format() {
+    let saved = RunSnapshot::capture(&mut mid_run());
+    let text = saved.to_ron().unwrap();
+    assert_eq!(RunSnapshot::from_ron(&text).unwrap(), saved);
+}
+
+#[test]
+fn save_then_load_gives_an_equivalent_world() {
+    let saved = RunSnapshot::capture(&mut mid_run());
+    let loaded = RunSnapshot::from_ron(&saved.to_ron().unwrap()).unwrap();
+
+    // Load over a run that has already gone elsewhere
+    let mut world = fresh_run();
+    for _ in 0..6 {
+        spawn_enemy_in_world(&mut world, EnemyKind::Orbie, Transform::default());
+    }
+    world.spawn().insert(RunScoped);
+    assert_ne!(RunSnapshot::capture(&mut world), saved);
+
+    loaded.restore(&mut world);
+    assert_eq!(RunSnapshot::capture(&mut world), saved);
+    let enemies = world.query::<&Enemy>().iter(&world).count();
+    let run_scoped = world.query::<&RunScoped>().iter(&world).count();
+    assert_eq!(enemies, EnemyKind::all().len());
+    assert_eq!(run_scoped, EnemyKind::all().len());
+}
+
+#[test]
+fn loaded_run_rolls_the_same_numbers() {
+    let mut original = mid_run();
+    let saved = RunSnapshot::capture(&mut original);
+    let mut world = fresh_run();
+    saved.restore(&mut world);
+
+    let rolls = |world: &mut World| {
+        let mut rng = world.resource_mut::<GameRng>();
+        (0..8).map(|_| rng.0.gen::<u32>()).collect::<Vec<_>>()
+    };
+    assert_eq!(rolls(&mut world), rolls(&mut original));
+}
@@ -0,0 +1,172 @@
+mod support;
+
+use bevy::{app::AppExit, prelude::*};
+use game::{
+    run::launch::LaunchOptions, Action, EnemyKind, GameMode, GameState, ModeState, ScoreboardEvent,
+    WeaponKind,
+};
+use support::Scenario;
+
+#[test]
+fn one_primary_hit_kills_an_orbie() {
+    let mut scenario = Scenario::new();
+    let position = scenario.in_sight(20.0);
+    let orbie = scenario.spawn_enemy(EnemyKind::Orbie, position);
+    scenario.step(2);
+
+    scenario.tap(Action::Fire);
+    scenario.step(2);
+
+    assert!(scenario.enemy(orbie).health <= 0);
+    assert_eq!(scenario.kills(), 1);
+    assert_eq!(scenario.count(ScoreboardEvent::Kill(EnemyKind::Orbie)), 1);
+    assert_eq!(scenario.count(ScoreboardEvent::Hit(WeaponKind::Primary)), 1);
+}
+
+#[test]
+fn primary_pierces_with_falloff() {
+    let mut scenario = Scenario::new();
+    let front = scenario.in_sight(20.0);
+    let back = scenario.in_sight(35.0);
+    scenario.spawn_enemy(EnemyKind::Orbie, front);
+    let behind = scenario.spawn_enemy(EnemyKind::Orbie, back);
+    scenario.step(2);
+
+    scenario.tap(Action::Fire);
+    scenario.step(2);
+
+    // The second Orbie takes 60% of the first's damage, not enough to kill it
+    assert_eq!(scenario.enemy(behind).health, 1000 - 601);
+    assert_eq!(scenario.kills(), 1);
+    assert_eq!(scenario.count(ScoreboardEvent::Hit(WeaponKind::Primary)), 1);
+}
+
+#[test]
+fn three_secondary_hits_kill_an_orbie() {
+    let mut scenario = Scenario::new();
+    let position = scenario.in_sight(20.0);
+    let orbie = scenario.spawn_enemy(EnemyKind::Orbie, position);
+    scenario.step(2);
+
+    scenario.hold(Action::AltFire);
+    let mut frames = 0;
+    while scenario.enemy(orbie).health > 0 {
+        assert!(frames < 60, "the Orbie outlived a second of secondary fire");
+        scenario.step(1);
+        frames += 1;
+    }
+    scenario.release(Action::AltFire);
+    scenario.step(2);
+
+    assert_eq!(scenario.enemy(orbie).health, 1000 - 3 * 334);
+    assert_eq!(
+        scenario.count(ScoreboardEvent::Hit(WeaponKind::Secondary)),
+        3
+    );
+    assert_eq!(scenario.kills(), 1);
+}
+
+#[test]
+fn direct_bullet_hit_deals_full_damage() {
+    let mut scenario = Scenario::new();
+    let before = scenario.player_hit_points();
+    let from = scenario.player_position() + Vec3::new(0.0, 0.0, -10.0);
+
+    scenario.shoot_bullet(from, Vec3::Z, 100.0, 8.0);
+    scenario.step(30);
+
+    assert_eq!(scenario.player_hit_points(), before - 100.0);
+}
+
+/// Shoots a bullet sideways into a wall 5 m from the player, returning the hit points it took
+fn splash_damage(splash_radius: f32) -> f32 {
+    let mut scenario = Scenario::new();
+    let player = scenario.player_position();
+    scenario.spawn_level_collider(player + Vec3::new(6.5, 0.0, 0.0), Vec3::new(0.5, 5.0, 5.0));
+    let before = scenario.player_hit_points();
+
+    scenario.shoot_bullet(
+        player + Vec3::new(3.0, 0.0, 0.0),
+        Vec3::X,
+        100.0,
+        splash_radius,
+    );
+    scenario.step(30);
+
+    before - scenario.player_hit_points()
+}
+
+#[test]
+fn splash_deals_half_damage_inside_its_radius() {
+    assert_eq!(splash_damage(8.0), 50.0);
+}
+
+#[test]
+fn splash_outside_its_radius_does_nothing() {
+    assert_eq!(splash_damage(3.0), 0.0);
+}
+
+#[test]
+fn boss_rush_bonus_reaches_the_final_score() {
+    let mut scenario = Scenario::with_mode(GameMode::BossRush);
+    // Firing starts the run
+    scenario.tap(Action::Fire);
+    scenario
+        .app
+        .world
+        .resource_mut::<ModeState>()
+        .bosses_defeated = 8;
+    scenario.step(1);
+
+    assert_eq!(
+        *scenario.app.world.resource::<State<GameState>>().current(),
+        GameState::GameOver
+    );
+    let bonus = scenario
+        .scoreboard_events()
+        .iter()
+        .find_map(|event| match event {
+            ScoreboardEvent::Bonus(points) => Some(*points),
+            _ => None,
+        })
+        .expect("Beating every boss pays a bonus");
+    assert!(bonus > 0);
+    // Nothing was killed, the bonus is the whole score
+    assert_eq!(scenario.scoreboard().bonus, bonus);
+    assert_eq!(scenario.scoreboard().score(), bonus);
+}
+
+#[test]
+fn frames_end_when_the_player_dies_first() {
+    let mut scenario = Scenario::new();
+    scenario.app.insert_resource(LaunchOptions {
+        frames: Some(100_000),
+        ..Default::default()
+    });
+    let from = scenario.player_position() + Vec3::new(0.0, 0.0, -10.0);
+    scenario.shoot_bullet(from, Vec3::Z, 100_000.0, 8.0);
+
+    let mut exited = false;
+    for _ in 0..60 {
+        scenario.step(1);
+        let events = scenario.app.world.resource::<Events<AppExit>>();
+        if events.get_reader().iter(events).next().is_some() {
+            exited = true;
+            break;
+        }
+    }
+    assert!(scenario.player().health <= 0.0);
+    assert!(exited, "--frames kept waiting after the player died");
+}
+
+#[test]
+fn dying_as_the_game_is_paused_ends_the_run() {
+    let mut scenario = Scenario::new();
+    scenario.player_mut().health = 0.0;
+    scenario.tap(Action::Menu);
+    scenario.step(1);
+
+    let state = scenario.app.world.resource::<State<GameState>>();
+    assert_eq!(*state.current(), GameState::GameOver);
+    assert!(!state.inactives().contains(&GameState::Paused));
+}
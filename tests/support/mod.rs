@@ -0,0 +1,222 @@
+//! A headless run of the game for scripted scenarios
+
+// Not every test file uses every helper
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use game::{
+    level_collision_layers,
+    run::{replay::ReplayRecorder, ResetRun},
+    spawn_enemy_in_world, Action, ActionInputSystem, ActionState, BulletBundle, Enemy, EnemyKind,
+    GameClock, GameMode, GameState, HeadlessPlugins, Player, Scoreboard, ScoreboardEvent,
+    SimulationPlugins,
+};
+use heron::{CollisionShape, RigidBody};
+
+/// Every frame steps by this, so scenarios play out the same on any machine
+pub const STEP: Duration = Duration::from_micros(16_667);
+/// Frames for the player to drop onto the floor and the weapons to be ready to fire
+const SETTLE_FRAMES: usize = 60;
+
+/// Actions held down until released, pressed after the input devices are read
+#[derive(Default)]
+struct ScriptedActions(Vec<Action>);
+
+/// Every [`ScoreboardEvent`] since the scenario started
+#[derive(Default)]
+struct RecordedScoreboardEvents(Vec<ScoreboardEvent>);
+
+pub struct Scenario {
+    pub app: App,
+}
+
+impl Scenario {
+    /// Starts a run with the player standing at the start of the level, no enemies around
+    pub fn new() -> Self {
+        Scenario::with_mode(GameMode::Survival)
+    }
+
+    /// Like [`Scenario::new`], as if `mode` had been picked on the start menu
+    pub fn with_mode(mode: GameMode) -> Self {
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugins)
+            .add_plugins(SimulationPlugins)
+            .init_resource::<ScriptedActions>()
+            .init_resource::<RecordedScoreboardEvents>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                press_scripted_actions.after(ActionInputSystem),
+            )
+            .add_system_to_stage(CoreStage::Last, record_scoreboard_events);
+        // Keeps the recordings of test runs out of the player's replays
+        app.insert_resource(mode)
+            .insert_resource(ReplayRecorder::default());
+        app.world.resource_mut::<GameClock>().fixed_step = Some(STEP);
+        app.world
+            .resource_mut::<State<GameState>>()
+            .set(GameState::Playing)
+            .unwrap();
+
+        let mut scenario = Scenario { app };
+        scenario.step(SETTLE_FRAMES);
+        scenario
+    }
+
+    /// An extra box of static level geometry
+    pub fn spawn_level_collider(&mut self, center: Vec3, half_extends: Vec3) -> Entity {
+        self.app
+            .world
+            .spawn()
+            .insert_bundle((
+                Transform::from_translation(center),
+                GlobalTransform::default(),
+                RigidBody::Static,
+                CollisionShape::Cuboid {
+                    half_extends,
+                    border_radius: None,
+                },
+                level_collision_layers(),
+            ))
+            .id()
+    }
+
+    /// Throws the run away like the retry button does and lets the player settle again
+    pub fn reset(&mut self) {
+        self.app
+            .world
+            .resource_mut::<Events<ResetRun>>()
+            .send(ResetRun);
+        self.step(SETTLE_FRAMES);
+    }
+
+    pub fn spawn_enemy(&mut self, kind: EnemyKind, position: Vec3) -> Entity {
+        spawn_enemy_in_world(
+            &mut self.app.world,
+            kind,
+            Transform::from_translation(position),
+        )
+    }
+
+    /// Fires an enemy bullet, `damage` is for a direct hit
+    pub fn shoot_bullet(&mut self, from: Vec3, direction: Vec3, damage: f32, splash_radius: f32) {
+        self.app.world.spawn().insert_bundle(BulletBundle::shoot(
+            from,
+            direction,
+            80.0,
+            damage,
+            splash_radius,
+        ));
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Keeps `action` pressed on every frame until it is released
+    pub fn hold(&mut self, action: Action) {
+        self.app
+            .world
+            .resource_mut::<ScriptedActions>()
+            .0
+            .push(action);
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.app
+            .world
+            .resource_mut::<ScriptedActions>()
+            .0
+            .retain(|held| *held != action);
+    }
+
+    /// Presses `action` for a single frame
+    pub fn tap(&mut self, action: Action) {
+        self.hold(action);
+        self.step(1);
+        self.release(action);
+    }
+
+    pub fn player(&mut self) -> &Player {
+        let entity = self.player_entity();
+        self.app.world.get::<Player>(entity).unwrap()
+    }
+
+    pub fn player_mut(&mut self) -> Mut<Player> {
+        let entity = self.player_entity();
+        self.app.world.get_mut::<Player>(entity).unwrap()
+    }
+
+    pub fn player_position(&mut self) -> Vec3 {
+        let entity = self.player_entity();
+        self.app.world.get::<Transform>(entity).unwrap().translation
+    }
+
+    /// Health and shield together, what damage takes away from
+    pub fn player_hit_points(&mut self) -> f32 {
+        let player = self.player();
+        player.health + player.shield
+    }
+
+    /// A point `distance` straight ahead of the player's eyes, where the lasers go
+    pub fn in_sight(&mut self, distance: f32) -> Vec3 {
+        // Nobody has looked around yet, so the player faces down -Z with the eyes 1.82 up
+        self.player_position() + Vec3::new(0.0, 1.82, -distance)
+    }
+
+    pub fn enemy(&self, entity: Entity) -> &Enemy {
+        self.app.world.get::<Enemy>(entity).unwrap()
+    }
+
+    pub fn scoreboard(&mut self) -> &Scoreboard {
+        let entity = self
+            .app
+            .world
+            .query_filtered::<Entity, With<Scoreboard>>()
+            .iter(&self.app.world)
+            .next()
+            .expect("The scoreboard spawns when the run starts");
+        self.app.world.get::<Scoreboard>(entity).unwrap()
+    }
+
+    pub fn kills(&mut self) -> usize {
+        self.scoreboard().kills
+    }
+
+    pub fn scoreboard_events(&self) -> &[ScoreboardEvent] {
+        &self.app.world.resource::<RecordedScoreboardEvents>().0
+    }
+
+    /// How many times `event` was sent
+    pub fn count(&self, event: ScoreboardEvent) -> usize {
+        self.scoreboard_events()
+            .iter()
+            .filter(|sent| **sent == event)
+            .count()
+    }
+
+    fn player_entity(&mut self) -> Entity {
+        self.app
+            .world
+            .query_filtered::<Entity, With<Player>>()
+            .iter(&self.app.world)
+            .next()
+            .expect("The player spawns when the run starts")
+    }
+}
+
+fn press_scripted_actions(scripted: Res<ScriptedActions>, mut action_state: ResMut<ActionState>) {
+    for action in scripted.0.iter() {
+        action_state.press(*action);
+    }
+}
+
+fn record_scoreboard_events(
+    mut events: EventReader<ScoreboardEvent>,
+    mut recorded: ResMut<RecordedScoreboardEvents>,
+) {
+    recorded.0.extend(events.iter().copied());
+}